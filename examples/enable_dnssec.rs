@@ -0,0 +1,44 @@
+use cloud_dns::api::{
+    managed_zone_operations::OperationResult,
+    managed_zones::{DnsSecConfig, DnssecState},
+};
+
+#[tokio::main]
+async fn main() -> cloud_dns::Result<()> {
+    let project_id = std::env::var("PROJECT_ID").expect("PROJECT_ID env variable is required");
+    let managed_zone =
+        std::env::var("MANAGED_ZONE").expect("MANAGED_ZONE env variable is required");
+
+    let service = tower::ServiceBuilder::new().service(
+        hyper::Client::builder().build(
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_webpki_roots()
+                .https_only()
+                .enable_http1()
+                .enable_http2()
+                .build(),
+        ),
+    );
+
+    let client = cloud_dns::DnsClient::new(service, project_id.as_str());
+
+    let zone = client.managed_zones().get(managed_zone.clone()).await?;
+    let zone = zone.dnssec_config(DnsSecConfig {
+        state: Some(DnssecState::On),
+        ..DnsSecConfig::default()
+    });
+
+    let operation = client.managed_zones().patch(&managed_zone, zone).await?;
+
+    let result = client
+        .managed_zone_operations()
+        .poller(&managed_zone)
+        .wait_for_result(operation)
+        .await?;
+
+    if let Some(OperationResult::Zone(zone)) = result {
+        println!("{:#?}", zone.dnssec_config);
+    }
+
+    Ok(())
+}
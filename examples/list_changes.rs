@@ -17,7 +17,7 @@ async fn main() -> cloud_dns::Result<()> {
 
     let response = cloud_dns::DnsClient::new(service, project_id.as_str())
         .changes()
-        .list(managed_zone.as_str())
+        .list(managed_zone.as_str(), &Default::default())
         .await?;
 
     println!("{:#?}", response);
@@ -16,8 +16,9 @@ async fn main() -> cloud_dns::Result<()> {
     );
 
     let response = cloud_dns::DnsClient::new(service, project_id.as_str())
-        .changes()
-        .list(managed_zone.as_str())
+        .resource_record_sets()
+        .list_all(managed_zone.as_str(), Default::default())
+        .collect_all()
         .await?;
 
     println!("{:#?}", response);
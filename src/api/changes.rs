@@ -1,22 +1,308 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+};
+
 use serde::{Deserialize, Serialize};
 
-use crate::{DnsClient, Result};
+use crate::{
+    error::DnsError,
+    pagination::{
+        page_request_methods, with_query, ListRequest, ListResponse, ListStream, PageRequest,
+        SortOrder,
+    },
+    poll::{poll_until, PollOptions},
+    rdata::RecordType,
+    DnsClient, Result,
+};
 
-use super::{resource_record_sets::ResourceRecordSet, ListEnvelope};
+use super::{
+    resource_record_sets::{RRSetRoutingPolicy, ResourceRecordSet},
+    ListEnvelope,
+};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Change {
-    pub kind: String, // "dns#change"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#change"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub additions: Vec<ResourceRecordSet>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub deletions: Vec<ResourceRecordSet>,
-    pub start_time: String,
-    pub id: String,
-    pub status: Status,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_serving: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Change {
+    /// A change that atomically deletes `deletions` and adds `additions`.
+    ///
+    /// Deletions must exactly match the current record sets, including their
+    /// TTL and rrdatas.
+    pub fn new(additions: Vec<ResourceRecordSet>, deletions: Vec<ResourceRecordSet>) -> Self {
+        Self {
+            additions,
+            deletions,
+            ..Self::default()
+        }
+    }
+
+    pub fn addition(mut self, record_set: ResourceRecordSet) -> Self {
+        self.additions.push(record_set);
+        self
+    }
+
+    pub fn deletion(mut self, record_set: ResourceRecordSet) -> Self {
+        self.deletions.push(record_set);
+        self
+    }
+
+    /// A reviewable summary of the change, see [`ChangePlan`].
+    pub fn plan(&self) -> ChangePlan {
+        ChangePlan::new(self)
+    }
+}
+
+/// The effect of a [`Change`] on each record set it touches.
+///
+/// A deletion and an addition of the same name and type are shown as a single
+/// update. The `Display` implementation renders a diff for reviewers:
+///
+/// ```text
+/// + new.example.com. CNAME (ttl 300)
+///     + www.example.com.
+/// ~ www.example.com. A (ttl 300 -> 60)
+///       192.0.2.1
+///     - 192.0.2.2
+///     + 192.0.2.3
+/// - old.example.com. A (ttl 300)
+///     - 192.0.2.4
+///
+/// Plan: 1 to add, 1 to change, 1 to delete.
+/// ```
+///
+/// It serializes to JSON for tools, as
+/// `{"summary": {"add": 1, ...}, "changes": [{"action": "update", ...}]}`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChangePlan {
+    pub summary: PlanSummary,
+    /// The touched record sets, ordered by name and type.
+    pub changes: Vec<PlannedRecordSet>,
+}
+
+impl ChangePlan {
+    pub fn new(change: &Change) -> Self {
+        let key =
+            |rrset: &ResourceRecordSet| (rrset.name.to_ascii_lowercase(), rrset.r#type.clone());
+
+        // Deletions are kept in order per key, so that duplicates, which the
+        // API rejects, still show up in the plan.
+        let mut before: BTreeMap<_, VecDeque<_>> = BTreeMap::new();
+        for deletion in &change.deletions {
+            before.entry(key(deletion)).or_default().push_back(deletion);
+        }
+
+        let mut changes: Vec<_> = change
+            .additions
+            .iter()
+            .map(|addition| {
+                let deletion = before.get_mut(&key(addition)).and_then(VecDeque::pop_front);
+                PlannedRecordSet::new(deletion, Some(addition))
+            })
+            .collect();
+        changes.extend(
+            before
+                .into_values()
+                .flatten()
+                .map(|deletion| PlannedRecordSet::new(Some(deletion), None)),
+        );
+        changes.sort_by(|a, b| {
+            (a.name.to_ascii_lowercase(), &a.r#type).cmp(&(b.name.to_ascii_lowercase(), &b.r#type))
+        });
+
+        let mut summary = PlanSummary::default();
+        for planned in &changes {
+            match planned.action {
+                PlanAction::Add => summary.add += 1,
+                PlanAction::Update => summary.update += 1,
+                PlanAction::Delete => summary.delete += 1,
+            }
+        }
+
+        Self { summary, changes }
+    }
+
+    /// Whether the change has no effect.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for ChangePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for planned in &self.changes {
+            writeln!(f, "{}", planned)?;
+        }
+
+        if !self.changes.is_empty() {
+            writeln!(f)?;
+        }
+
+        write!(
+            f,
+            "Plan: {} to add, {} to change, {} to delete.",
+            self.summary.add, self.summary.update, self.summary.delete
+        )
+    }
+}
+
+/// The number of record sets a [`ChangePlan`] adds, updates and deletes.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanSummary {
+    pub add: usize,
+    pub update: usize,
+    pub delete: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Add,
+    Update,
+    Delete,
+}
+
+impl PlanAction {
+    /// The symbol prefixing the action in a diff, `+`, `~` or `-`.
+    pub fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Update => '~',
+            Self::Delete => '-',
+        }
+    }
+}
+
+/// The change to a single record set in a [`ChangePlan`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlannedRecordSet {
+    pub action: PlanAction,
+    pub name: String,
+    pub r#type: RecordType,
+    /// The record set before the change, `None` if it is added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<RecordSetState>,
+    /// The record set after the change, `None` if it is deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<RecordSetState>,
+}
+
+impl PlannedRecordSet {
+    fn new(before: Option<&ResourceRecordSet>, after: Option<&ResourceRecordSet>) -> Self {
+        let (action, rrset) = match (before, after) {
+            (Some(_), Some(after)) => (PlanAction::Update, after),
+            (None, Some(after)) => (PlanAction::Add, after),
+            (Some(before), None) => (PlanAction::Delete, before),
+            (None, None) => unreachable!("a planned record set is added, deleted or both"),
+        };
+
+        Self {
+            action,
+            name: rrset.name.clone(),
+            r#type: rrset.r#type.clone(),
+            before: before.map(RecordSetState::from),
+            after: after.map(RecordSetState::from),
+        }
+    }
+}
+
+impl fmt::Display for PlannedRecordSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let before = self.before.as_ref();
+        let after = self.after.as_ref();
+
+        write!(f, "{} {} {}", self.action.symbol(), self.name, self.r#type)?;
+        match (before.and_then(|s| s.ttl), after.and_then(|s| s.ttl)) {
+            (Some(old), Some(new)) if old != new => write!(f, " (ttl {} -> {})", old, new)?,
+            (Some(ttl), _) | (None, Some(ttl)) => write!(f, " (ttl {})", ttl)?,
+            (None, None) => {}
+        }
+
+        let old_rrdatas = before.map_or(&[][..], |s| &s.rrdatas[..]);
+        let new_rrdatas = after.map_or(&[][..], |s| &s.rrdatas[..]);
+
+        for rrdata in old_rrdatas {
+            if new_rrdatas.contains(rrdata) {
+                write!(f, "\n      {}", rrdata)?;
+            }
+        }
+        for rrdata in old_rrdatas {
+            if !new_rrdatas.contains(rrdata) {
+                write!(f, "\n    - {}", rrdata)?;
+            }
+        }
+        for rrdata in new_rrdatas {
+            if !old_rrdatas.contains(rrdata) {
+                write!(f, "\n    + {}", rrdata)?;
+            }
+        }
+
+        // Routing policies are rendered as compact JSON, as they are sent.
+        let old_policy = before.and_then(|s| s.routing_policy.as_ref());
+        let new_policy = after.and_then(|s| s.routing_policy.as_ref());
+        let policy = |policy| serde_json::to_string(policy).map_err(|_| fmt::Error);
+
+        match (old_policy, new_policy) {
+            (Some(old), Some(new)) if old == new => {
+                write!(f, "\n      routing policy {}", policy(old)?)?
+            }
+            (old, new) => {
+                if let Some(old) = old {
+                    write!(f, "\n    - routing policy {}", policy(old)?)?;
+                }
+                if let Some(new) = new {
+                    write!(f, "\n    + routing policy {}", policy(new)?)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The TTL, rrdatas and routing policy of a record set in a
+/// [`PlannedRecordSet`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordSetState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<i32>,
+    pub rrdatas: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_policy: Option<RRSetRoutingPolicy>,
+}
+
+impl From<&ResourceRecordSet> for RecordSetState {
+    fn from(rrset: &ResourceRecordSet) -> Self {
+        Self {
+            ttl: rrset.ttl,
+            rrdatas: rrset.rrdatas.clone(),
+            routing_policy: rrset.routing_policy.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Status {
     Done,
@@ -28,9 +314,83 @@ pub enum Status {
 pub struct Changes {
     #[serde(flatten)]
     pub envelope: ListEnvelope,
+    #[serde(default)]
     pub changes: Vec<Change>,
 }
 
+impl ListResponse for Changes {
+    type Item = Change;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.envelope.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Change> {
+        self.changes
+    }
+}
+
+/// The fields changes can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangesSortBy {
+    ChangeSequence,
+}
+
+impl ChangesSortBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ChangeSequence => "changeSequence",
+        }
+    }
+}
+
+/// The query parameters of a `list` call on [`ChangesHandler`].
+#[derive(Debug, Clone, Default)]
+pub struct ListChangesRequest {
+    page: PageRequest,
+    sort_by: Option<ChangesSortBy>,
+    sort_order: Option<SortOrder>,
+}
+
+impl ListChangesRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    page_request_methods!();
+
+    /// Sorts the changes by the given field.
+    pub fn sort_by(mut self, sort_by: ChangesSortBy) -> Self {
+        self.sort_by = Some(sort_by);
+        self
+    }
+
+    /// Sorts the changes in the given order.
+    pub fn sort_order(mut self, sort_order: SortOrder) -> Self {
+        self.sort_order = Some(sort_order);
+        self
+    }
+}
+
+impl ListRequest for ListChangesRequest {
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = self.page.query_pairs();
+
+        if let Some(sort_by) = self.sort_by {
+            pairs.push(("sortBy", sort_by.as_str().to_string()));
+        }
+        if let Some(sort_order) = self.sort_order {
+            pairs.push(("sortOrder", sort_order.as_str().to_string()));
+        }
+
+        pairs
+    }
+
+    fn set_page_token(&mut self, page_token: Option<String>) {
+        self.page.set_page_token(page_token);
+    }
+}
+
 pub struct ChangesHandler<'client> {
     client: &'client DnsClient,
 }
@@ -40,12 +400,25 @@ impl<'client> ChangesHandler<'client> {
         Self { client }
     }
 
-    pub async fn list(&self, managed_zone: &str) -> Result<Changes> {
-        let route = format!("managedZones/{}/changes", managed_zone);
+    pub async fn list(&self, managed_zone: &str, request: &ListChangesRequest) -> Result<Changes> {
+        let route = with_query(format!("managedZones/{}/changes", managed_zone), request);
 
         self.client.get(route).await
     }
 
+    /// Streams all changes to the zone, fetching further pages as needed.
+    pub fn list_all(
+        &self,
+        managed_zone: &str,
+        request: ListChangesRequest,
+    ) -> ListStream<'client, Change> {
+        ListStream::new::<Changes, _>(
+            self.client,
+            format!("managedZones/{}/changes", managed_zone),
+            request,
+        )
+    }
+
     pub async fn get(&self, managed_zone: &str, change_id: &str) -> Result<Change> {
         let route = format!("managedZones/{}/changes/{}", managed_zone, change_id);
 
@@ -57,4 +430,43 @@ impl<'client> ChangesHandler<'client> {
 
         self.client.post(route, Some(&change)).await
     }
+
+    /// Creates the change and waits until it is [`Status::Done`], see
+    /// [`ChangesHandler::wait_until_done`].
+    pub async fn create_and_wait(
+        &self,
+        managed_zone: &str,
+        change: Change,
+        options: &PollOptions,
+    ) -> Result<Change> {
+        let change = self.create(managed_zone, change).await?;
+
+        match (&change.status, &change.id) {
+            (Some(Status::Done), _) => Ok(change),
+            (_, Some(change_id)) => self.wait_until_done(managed_zone, change_id, options).await,
+            (_, None) => Err(DnsError::Other {
+                source: "the created change has no id".into(),
+            }),
+        }
+    }
+
+    /// Polls the change with exponential backoff until it is [`Status::Done`],
+    /// at which point its records are being served.
+    ///
+    /// Fails with [`DnsError::Timeout`] if the change isn't done within the
+    /// timeout of `options`.
+    pub async fn wait_until_done(
+        &self,
+        managed_zone: &str,
+        change_id: &str,
+        options: &PollOptions,
+    ) -> Result<Change> {
+        poll_until(
+            options,
+            &format!("change {} in {}", change_id, managed_zone),
+            || self.get(managed_zone, change_id),
+            |change| change.status == Some(Status::Done),
+        )
+        .await
+    }
 }
@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{DnsClient, Result};
+use crate::{
+    pagination::{
+        page_request_methods, with_query, ListRequest, ListResponse, ListStream, PageRequest,
+    },
+    DnsClient, Result,
+};
 
 use super::ListEnvelope;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DnsKey {
     pub kind: String, // "dns#dnsKey"
@@ -16,18 +21,20 @@ pub struct DnsKey {
     pub is_active: bool,
     pub r#type: KeyType,
     pub key_tag: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub digests: Vec<Digest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum KeyType {
     KeySigning,
     ZoneSigning,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum Algorithm {
     Ecdsap256sha256,
@@ -37,14 +44,14 @@ pub enum Algorithm {
     Rsasha512,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Digest {
     pub r#type: DigestType,
     pub digest: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum DigestType {
     Sha1,
@@ -52,14 +59,75 @@ pub enum DigestType {
     Sha384,
 }
 
+impl DigestType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct DnsKeys {
     #[serde(flatten)]
     pub envelope: ListEnvelope,
+    #[serde(default)]
     pub dns_keys: Vec<DnsKey>,
 }
 
+impl ListResponse for DnsKeys {
+    type Item = DnsKey;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.envelope.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<DnsKey> {
+        self.dns_keys
+    }
+}
+
+/// The query parameters of a `list` call on [`DnsKeysHandler`].
+#[derive(Debug, Clone, Default)]
+pub struct ListDnsKeysRequest {
+    page: PageRequest,
+    digest_type: Vec<DigestType>,
+}
+
+impl ListDnsKeysRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    page_request_methods!();
+
+    /// Only computes the digests of the given types, defaults to `sha256`.
+    pub fn digest_type(mut self, digest_type: impl IntoIterator<Item = DigestType>) -> Self {
+        self.digest_type = digest_type.into_iter().collect();
+        self
+    }
+}
+
+impl ListRequest for ListDnsKeysRequest {
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = self.page.query_pairs();
+
+        if !self.digest_type.is_empty() {
+            let digest_types: Vec<_> = self.digest_type.iter().map(|d| d.as_str()).collect();
+            pairs.push(("digestType", digest_types.join(",")));
+        }
+
+        pairs
+    }
+
+    fn set_page_token(&mut self, page_token: Option<String>) {
+        self.page.set_page_token(page_token);
+    }
+}
+
 pub struct DnsKeysHandler<'client> {
     client: &'client DnsClient,
 }
@@ -69,15 +137,25 @@ impl<'client> DnsKeysHandler<'client> {
         Self { client }
     }
 
-    pub async fn list(&self, managed_zone: &str) -> Result<DnsKeys> {
-        let route = format!(
-            "managedZones/{managed_zone}/dnsKeys",
-            managed_zone = managed_zone,
-        );
+    pub async fn list(&self, managed_zone: &str, request: &ListDnsKeysRequest) -> Result<DnsKeys> {
+        let route = with_query(format!("managedZones/{}/dnsKeys", managed_zone), request);
 
         self.client.get(route).await
     }
 
+    /// Streams all DNSSEC keys of the zone, fetching further pages as needed.
+    pub fn list_all(
+        &self,
+        managed_zone: &str,
+        request: ListDnsKeysRequest,
+    ) -> ListStream<'client, DnsKey> {
+        ListStream::new::<DnsKeys, _>(
+            self.client,
+            format!("managedZones/{}/dnsKeys", managed_zone),
+            request,
+        )
+    }
+
     pub async fn get(&self, managed_zone: &str, dns_key_id: &str) -> Result<DnsKey> {
         let route = format!(
             "managedZones/{managed_zone}/dnsKeys/{dns_key_id}",
@@ -0,0 +1,187 @@
+//! The IAM policies controlling access to individual managed zones, see
+//! [`ManagedZonesHandler::get_iam_policy`](super::managed_zones::ManagedZonesHandler::get_iam_policy).
+
+use serde::{Deserialize, Serialize};
+
+/// The policy version supporting conditional role bindings. Requesting it is
+/// harmless for policies without conditions.
+pub const CONDITIONAL_POLICY_VERSION: i32 = 3;
+
+/// Binds members, eg. `user:alice@example.com` or
+/// `group:dns-admins@example.com`, to roles such as `roles/dns.admin`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IamPolicy {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub version: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<Binding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audit_configs: Vec<AuditConfig>,
+    /// Identifies the version of the policy that was read. Setting a policy
+    /// fails if its etag no longer matches the current one, so that
+    /// concurrent read-modify-write cycles don't overwrite each other.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+impl IamPolicy {
+    /// Grants `role` to `member` without a condition.
+    ///
+    /// Returns whether the policy changed, ie. `false` if the member already
+    /// had the role unconditionally.
+    pub fn add_member(&mut self, role: &str, member: &str) -> bool {
+        match self
+            .bindings
+            .iter_mut()
+            .find(|binding| binding.role == role && binding.condition.is_none())
+        {
+            Some(binding) if binding.members.iter().any(|m| m == member) => false,
+            Some(binding) => {
+                binding.members.push(member.to_string());
+                true
+            }
+            None => {
+                self.bindings.push(Binding::new(role, vec![member]));
+                true
+            }
+        }
+    }
+
+    /// Revokes the unconditional grant of `role` to `member`, removing the
+    /// binding if it has no members left. Conditional grants are kept.
+    ///
+    /// Returns whether the policy changed.
+    pub fn remove_member(&mut self, role: &str, member: &str) -> bool {
+        let mut changed = false;
+
+        for binding in &mut self.bindings {
+            if binding.role == role && binding.condition.is_none() {
+                let len = binding.members.len();
+                binding.members.retain(|m| m != member);
+                changed |= binding.members.len() != len;
+            }
+        }
+        self.bindings.retain(|binding| !binding.members.is_empty());
+
+        changed
+    }
+
+    /// The members that have `role`, with or without a condition.
+    pub fn members(&self, role: &str) -> impl Iterator<Item = &str> {
+        let role = role.to_string();
+
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.role == role)
+            .flat_map(|binding| binding.members.iter().map(String::as_str))
+    }
+}
+
+fn is_zero(version: &i32) -> bool {
+    *version == 0
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Binding {
+    pub role: String,
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Only grants the role while the condition holds, which requires policy
+    /// version [`CONDITIONAL_POLICY_VERSION`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+}
+
+impl Binding {
+    pub fn new<I, S>(role: &str, members: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            role: role.to_string(),
+            members: members.into_iter().map(Into::into).collect(),
+            condition: None,
+        }
+    }
+
+    pub fn condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+/// A condition written in the Common Expression Language, eg.
+/// `request.time < timestamp("2030-01-01T00:00:00Z")`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+impl Condition {
+    pub fn new(title: &str, expression: &str) -> Self {
+        Self {
+            expression: expression.to_string(),
+            title: Some(title.to_string()),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditConfig {
+    pub service: String,
+    #[serde(default)]
+    pub audit_log_configs: Vec<AuditLogConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogConfig {
+    /// `ADMIN_READ`, `DATA_WRITE` or `DATA_READ`.
+    pub log_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exempted_members: Vec<String>,
+}
+
+/// The body of a `getIamPolicy` request.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetIamPolicyRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<GetPolicyOptions>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPolicyOptions {
+    pub requested_policy_version: i32,
+}
+
+/// The body of a `setIamPolicy` request.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetIamPolicyRequest {
+    pub policy: IamPolicy,
+    /// The fields of the policy to update, `bindings,etag` if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_mask: Option<String>,
+}
+
+/// The body of a `testIamPermissions` request and its response.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TestIamPermissions {
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
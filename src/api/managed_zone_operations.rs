@@ -1,34 +1,86 @@
-use crate::{DnsClient, Result};
+use crate::{
+    pagination::{
+        page_request_methods, with_query, ListRequest, ListResponse, ListStream, PageRequest,
+    },
+    poll::{poll_until, PollOptions},
+    DnsClient, Result,
+};
 
 use super::managed_zones::ManagedZone;
 use super::{dns_keys::DnsKey, ListEnvelope};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ManagedZoneOperation {
     pub kind: String, // "dns#operation"
     pub id: String,
     pub start_time: String,
-    pub status: String,
+    pub status: OperationStatus,
     pub user: String,
     pub r#type: String,
-    pub zone_context: ZoneContext,
-    pub dns_key_context: DnsKeyContext,
+    /// Only set for operations on the zone itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_context: Option<ZoneContext>,
+    /// Only set for operations on a DNSSEC key of the zone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_key_context: Option<DnsKeyContext>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl ManagedZoneOperation {
+    pub fn is_done(&self) -> bool {
+        self.status == OperationStatus::Done
+    }
+
+    /// The resource as it is after the operation, `None` if the operation
+    /// deleted it.
+    pub fn result(self) -> Option<OperationResult> {
+        if let Some(context) = self.zone_context {
+            return context
+                .new_value
+                .map(|zone| OperationResult::Zone(Box::new(zone)));
+        }
+
+        self.dns_key_context
+            .and_then(|context| context.new_value)
+            .map(OperationResult::DnsKey)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Done,
+    Pending,
+}
+
+/// The resource an operation modified, as it is after the operation.
+#[derive(Debug, Clone)]
+pub enum OperationResult {
+    Zone(Box<ManagedZone>),
+    DnsKey(DnsKey),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ZoneContext {
-    pub old_value: ManagedZone,
-    pub new_value: ManagedZone,
+    /// Not set for operations that create the zone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<ManagedZone>,
+    /// Not set for operations that delete the zone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<ManagedZone>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DnsKeyContext {
-    pub old_value: DnsKey,
-    pub new_value: DnsKey,
+    /// Not set for operations that create the key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<DnsKey>,
+    /// Not set for operations that delete the key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<DnsKey>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -36,9 +88,75 @@ pub struct DnsKeyContext {
 pub struct ManagedZoneOperations {
     #[serde(flatten)]
     pub envelope: ListEnvelope,
+    #[serde(default)]
     pub operations: Vec<ManagedZoneOperation>,
 }
 
+impl ListResponse for ManagedZoneOperations {
+    type Item = ManagedZoneOperation;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.envelope.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<ManagedZoneOperation> {
+        self.operations
+    }
+}
+
+/// The fields operations can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationsSortBy {
+    StartTime,
+    Id,
+}
+
+impl OperationsSortBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::StartTime => "startTime",
+            Self::Id => "id",
+        }
+    }
+}
+
+/// The query parameters of a `list` call on [`ManagedZoneOperationsHandler`].
+#[derive(Debug, Clone, Default)]
+pub struct ListOperationsRequest {
+    page: PageRequest,
+    sort_by: Option<OperationsSortBy>,
+}
+
+impl ListOperationsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    page_request_methods!();
+
+    /// Sorts the operations by the given field.
+    pub fn sort_by(mut self, sort_by: OperationsSortBy) -> Self {
+        self.sort_by = Some(sort_by);
+        self
+    }
+}
+
+impl ListRequest for ListOperationsRequest {
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = self.page.query_pairs();
+
+        if let Some(sort_by) = self.sort_by {
+            pairs.push(("sortBy", sort_by.as_str().to_string()));
+        }
+
+        pairs
+    }
+
+    fn set_page_token(&mut self, page_token: Option<String>) {
+        self.page.set_page_token(page_token);
+    }
+}
+
 pub struct ManagedZoneOperationsHandler<'client> {
     client: &'client DnsClient,
 }
@@ -48,15 +166,29 @@ impl<'client> ManagedZoneOperationsHandler<'client> {
         Self { client }
     }
 
-    pub async fn list(&self, managed_zone: &str) -> Result<ManagedZoneOperations> {
-        let route = format!(
-            "managedZones/{managed_zone}/operations",
-            managed_zone = managed_zone,
-        );
+    pub async fn list(
+        &self,
+        managed_zone: &str,
+        request: &ListOperationsRequest,
+    ) -> Result<ManagedZoneOperations> {
+        let route = with_query(format!("managedZones/{}/operations", managed_zone), request);
 
         self.client.get(route).await
     }
 
+    /// Streams all operations on the zone, fetching further pages as needed.
+    pub fn list_all(
+        &self,
+        managed_zone: &str,
+        request: ListOperationsRequest,
+    ) -> ListStream<'client, ManagedZoneOperation> {
+        ListStream::new::<ManagedZoneOperations, _>(
+            self.client,
+            format!("managedZones/{}/operations", managed_zone),
+            request,
+        )
+    }
+
     pub async fn get(
         &self,
         managed_zone: &str,
@@ -70,4 +202,57 @@ impl<'client> ManagedZoneOperationsHandler<'client> {
 
         self.client.get(route).await
     }
+
+    /// Creates a poller that waits for operations on `managed_zone` to
+    /// complete, eg. those returned by
+    /// [`ManagedZonesHandler::patch`](super::managed_zones::ManagedZonesHandler::patch).
+    pub fn poller(&self, managed_zone: &str) -> OperationPoller<'client> {
+        OperationPoller {
+            handler: ManagedZoneOperationsHandler::new(self.client),
+            managed_zone: managed_zone.to_string(),
+            options: PollOptions::default(),
+        }
+    }
+}
+
+/// Drives a [`ManagedZoneOperation`] to completion by polling
+/// [`ManagedZoneOperationsHandler::get`].
+pub struct OperationPoller<'client> {
+    handler: ManagedZoneOperationsHandler<'client>,
+    managed_zone: String,
+    options: PollOptions,
+}
+
+impl<'client> OperationPoller<'client> {
+    pub fn options(mut self, options: PollOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Polls the operation until it is [`OperationStatus::Done`].
+    ///
+    /// Fails with [`DnsError::Timeout`](crate::error::DnsError::Timeout) if
+    /// the operation isn't done within the timeout of the poll options.
+    pub async fn wait(&self, operation: ManagedZoneOperation) -> Result<ManagedZoneOperation> {
+        if operation.is_done() {
+            return Ok(operation);
+        }
+
+        poll_until(
+            &self.options,
+            &format!("operation {} in {}", operation.id, self.managed_zone),
+            || self.handler.get(&self.managed_zone, &operation.id),
+            ManagedZoneOperation::is_done,
+        )
+        .await
+    }
+
+    /// Like [`OperationPoller::wait`], but returns the resulting zone or DNS
+    /// key, see [`ManagedZoneOperation::result`].
+    pub async fn wait_for_result(
+        &self,
+        operation: ManagedZoneOperation,
+    ) -> Result<Option<OperationResult>> {
+        Ok(self.wait(operation).await?.result())
+    }
 }
@@ -1,110 +1,300 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, net::IpAddr};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{DnsClient, Result};
+use crate::{
+    error::{DnsError, ErrorKind},
+    pagination::{
+        page_request_methods, with_query, ListRequest, ListResponse, ListStream, PageRequest,
+    },
+    DnsClient, Result,
+};
 
-use super::{managed_zone_operations::ManagedZoneOperation, ListEnvelope};
+use super::{
+    iam::{
+        GetIamPolicyRequest, GetPolicyOptions, IamPolicy, SetIamPolicyRequest, TestIamPermissions,
+        CONDITIONAL_POLICY_VERSION,
+    },
+    managed_zone_operations::ManagedZoneOperation,
+    ListEnvelope,
+};
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct ManagedZone {
-    pub kind: String, // "dns#managedZone"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZone"
     pub name: String,
     pub dns_name: String,
-    pub description: String,
-    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Output only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub name_servers: Vec<String>,
-    pub creation_time: String,
-    pub dnssec_config: DnsSecConfig,
-    pub name_server_set: String,
-    pub visibility: String,
-    pub private_visibility_config: PrivateVisibilityConfig,
-    pub forwarding_config: ForwardingConfig,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dnssec_config: Option<DnsSecConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_server_set: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_visibility_config: Option<PrivateVisibilityConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarding_config: Option<ForwardingConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub labels: HashMap<String, serde_json::Value>,
-    pub peering_config: PeeringConfig,
-    pub reverse_lookup_config: ReverseLookupConfig,
-    pub service_directory_config: ServiceDirectoryConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peering_config: Option<PeeringConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverse_lookup_config: Option<ReverseLookupConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_directory_config: Option<ServiceDirectoryConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloud_logging_config: Option<CloudLoggingConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
+impl ManagedZone {
+    /// A zone visible on the internet, `dns_name` must be fully qualified, eg.
+    /// `example.com.`.
+    pub fn public(name: &str, dns_name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            dns_name: dns_name.to_string(),
+            visibility: Some(Visibility::Public),
+            ..Self::default()
+        }
+    }
+
+    /// A zone only visible from the given VPC networks, eg.
+    /// `https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default`.
+    pub fn private<I, S>(name: &str, dns_name: &str, network_urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.to_string(),
+            dns_name: dns_name.to_string(),
+            visibility: Some(Visibility::Private),
+            private_visibility_config: Some(PrivateVisibilityConfig {
+                kind: None,
+                networks: network_urls
+                    .into_iter()
+                    .map(|network_url| PrivateVisibilityConfigNetwork {
+                        kind: None,
+                        network_url: network_url.into(),
+                    })
+                    .collect(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.labels
+            .insert(key.to_string(), serde_json::Value::from(value));
+        self
+    }
+
+    pub fn dnssec_config(mut self, dnssec_config: DnsSecConfig) -> Self {
+        self.dnssec_config = Some(dnssec_config);
+        self
+    }
+
+    /// Logs the queries answered from the zone to Cloud Logging.
+    pub fn enable_logging(mut self, enable_logging: bool) -> Self {
+        self.cloud_logging_config = Some(CloudLoggingConfig {
+            kind: None,
+            enable_logging,
+        });
+        self
+    }
+}
+
+/// Who can query a zone.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Anyone on the internet.
+    Public,
+    /// Only the networks of its [`PrivateVisibilityConfig`].
+    Private,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudLoggingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneCloudLoggingConfig"
+    #[serde(default)]
+    pub enable_logging: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct ServiceDirectoryConfig {
-    pub kind: String, // "dns#managedZoneServiceDirectoryConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneServiceDirectoryConfig"
     pub namespace: ServiceDirectoryConfigNamespace,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct ServiceDirectoryConfigNamespace {
-    pub kind: String, // "dns#managedZoneServiceDirectoryConfigNamespace"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneServiceDirectoryConfigNamespace"
     pub namespace_url: String,
-    pub deletion_time: String,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deletion_time: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ReverseLookupConfig {
-    pub kind: String, // "dns#managedZoneReverseLookupConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneReverseLookupConfig"
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PeeringConfig {
-    pub kind: String, // "dns#managedZonePeeringConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZonePeeringConfig"
     pub target_network: PeeringConfigTargetNetwork,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PeeringConfigTargetNetwork {
-    pub kind: String, // "dns#managedZonePeeringConfigTargetNetwork"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZonePeeringConfigTargetNetwork"
     pub network_url: String,
-    pub deactivate_time: String,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deactivate_time: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PrivateVisibilityConfig {
-    pub kind: String, // "dns#managedZonePrivateVisibilityConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZonePrivateVisibilityConfig"
+    #[serde(default)]
     pub networks: Vec<PrivateVisibilityConfigNetwork>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ForwardingConfig {
-    pub kind: String, // "dns#managedZoneForwardingConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneForwardingConfig"
+    #[serde(default)]
     pub target_name_servers: Vec<ForwardingConfigNameServerTarget>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ForwardingConfigNameServerTarget {
-    pub kind: String, // "dns#managedZoneForwardingConfigNameServerTarget"
-    pub ipv4_address: String,
-    pub forwarding_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneForwardingConfigNameServerTarget"
+    /// Exactly one of `ipv4_address` and `ipv6_address` must be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv4_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarding_path: Option<ForwardingPath>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl ForwardingConfigNameServerTarget {
+    /// A name server at `address`, forwarded to according to its address.
+    pub fn new(address: IpAddr) -> Self {
+        let (ipv4_address, ipv6_address) = match address {
+            IpAddr::V4(address) => (Some(address.to_string()), None),
+            IpAddr::V6(address) => (None, Some(address.to_string())),
+        };
+
+        Self {
+            kind: None,
+            ipv4_address,
+            ipv6_address,
+            forwarding_path: None,
+        }
+    }
+
+    pub fn forwarding_path(mut self, forwarding_path: ForwardingPath) -> Self {
+        self.forwarding_path = Some(forwarding_path);
+        self
+    }
+}
+
+/// How queries reach a forwarding target.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardingPath {
+    /// Private (RFC 1918) addresses are reached through the VPC network, all
+    /// others through the internet.
+    Default,
+    /// Always through the VPC network.
+    Private,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PrivateVisibilityConfigNetwork {
-    pub kind: String, // "dns#managedZonePrivateVisibilityConfigNetwork"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZonePrivateVisibilityConfigNetwork"
     pub network_url: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DnsSecConfig {
-    pub kind: String, // "dns#managedZoneDnsSecConfig"
-    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#managedZoneDnsSecConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<DnssecState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub default_key_specs: Vec<DefaultKeySpec>,
-    pub non_existence: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_existence: Option<NonExistence>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DnssecState {
+    On,
+    Off,
+    /// Serves the signatures of a zone being transferred to or from another
+    /// DNS provider, without signing it.
+    Transfer,
+}
+
+/// How the absence of names is proven in signed zones.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NonExistence {
+    Nsec,
+    Nsec3,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DefaultKeySpec {
-    pub kind: String, // "dns#dnsKeySpec"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#dnsKeySpec"
     pub key_type: String,
     pub algorithm: String,
     pub key_length: u64,
@@ -115,24 +305,92 @@ pub struct DefaultKeySpec {
 pub struct ManagedZones {
     #[serde(flatten)]
     pub envelope: ListEnvelope,
+    #[serde(default)]
     pub managed_zones: Vec<ManagedZone>,
 }
 
+impl ListResponse for ManagedZones {
+    type Item = ManagedZone;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.envelope.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<ManagedZone> {
+        self.managed_zones
+    }
+}
+
+/// The query parameters of a `list` call on [`ManagedZonesHandler`].
+#[derive(Debug, Clone, Default)]
+pub struct ListManagedZonesRequest {
+    page: PageRequest,
+    dns_name: Option<String>,
+}
+
+impl ListManagedZonesRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    page_request_methods!();
+
+    /// Only returns zones for the given DNS name, eg. `example.com.`.
+    pub fn dns_name(mut self, dns_name: impl Into<String>) -> Self {
+        self.dns_name = Some(dns_name.into());
+        self
+    }
+}
+
+impl ListRequest for ListManagedZonesRequest {
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = self.page.query_pairs();
+
+        if let Some(dns_name) = &self.dns_name {
+            pairs.push(("dnsName", dns_name.clone()));
+        }
+
+        pairs
+    }
+
+    fn set_page_token(&mut self, page_token: Option<String>) {
+        self.page.set_page_token(page_token);
+    }
+}
+
 pub struct ManagedZonesHandler<'client> {
     client: &'client DnsClient,
+    conflict_retries: u32,
 }
 
 impl<'client> ManagedZonesHandler<'client> {
     pub(crate) fn new(client: &'client DnsClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            conflict_retries: 0,
+        }
     }
 
-    pub async fn list(&self) -> Result<ManagedZones> {
-        let route = "managedZones".to_string();
+    /// Retries [`add_iam_member`](Self::add_iam_member) and
+    /// [`remove_iam_member`](Self::remove_iam_member) up to `retries` times
+    /// when the IAM policy is changed concurrently, re-reading it each time.
+    /// They are not retried by default.
+    pub fn retry_conflicts(mut self, retries: u32) -> Self {
+        self.conflict_retries = retries;
+        self
+    }
+
+    pub async fn list(&self, request: &ListManagedZonesRequest) -> Result<ManagedZones> {
+        let route = with_query("managedZones".to_string(), request);
 
         self.client.get(route).await
     }
 
+    /// Streams all managed zones in the project, fetching further pages as needed.
+    pub fn list_all(&self, request: ListManagedZonesRequest) -> ListStream<'client, ManagedZone> {
+        ListStream::new::<ManagedZones, _>(self.client, "managedZones".to_string(), request)
+    }
+
     pub async fn get(&self, managed_zone: String) -> Result<ManagedZone> {
         let route = format!("managedZones/{managed_zone}", managed_zone = managed_zone,);
 
@@ -175,4 +433,117 @@ impl<'client> ManagedZonesHandler<'client> {
 
         self.client.put(route, Some(&managed_zone)).await
     }
+
+    /// The IAM policy of the zone, in the version supporting conditional role
+    /// bindings.
+    pub async fn get_iam_policy(&self, managed_zone: &str) -> Result<IamPolicy> {
+        let route = format!(
+            "managedZones/{managed_zone}:getIamPolicy",
+            managed_zone = managed_zone,
+        );
+        let request = GetIamPolicyRequest {
+            options: Some(GetPolicyOptions {
+                requested_policy_version: CONDITIONAL_POLICY_VERSION,
+            }),
+        };
+
+        self.client.post(route, Some(&request)).await
+    }
+
+    /// Replaces the IAM policy of the zone. If `policy` has an etag, this
+    /// fails with a conflict unless it matches the current policy's.
+    pub async fn set_iam_policy(&self, managed_zone: &str, policy: IamPolicy) -> Result<IamPolicy> {
+        let route = format!(
+            "managedZones/{managed_zone}:setIamPolicy",
+            managed_zone = managed_zone,
+        );
+        let request = SetIamPolicyRequest {
+            policy,
+            update_mask: None,
+        };
+
+        self.client.post(route, Some(&request)).await
+    }
+
+    /// Returns those of `permissions`, eg. `dns.resourceRecordSets.update`,
+    /// that the caller has on the zone.
+    pub async fn test_iam_permissions<I, S>(
+        &self,
+        managed_zone: &str,
+        permissions: I,
+    ) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let route = format!(
+            "managedZones/{managed_zone}:testIamPermissions",
+            managed_zone = managed_zone,
+        );
+        let request = TestIamPermissions {
+            permissions: permissions.into_iter().map(Into::into).collect(),
+        };
+
+        let response: TestIamPermissions = self.client.post(route, Some(&request)).await?;
+        Ok(response.permissions)
+    }
+
+    /// Grants `role` on the zone to `member`, eg. `group:team@example.com`,
+    /// and returns the resulting policy.
+    ///
+    /// The policy is read and written back with its etag, so this fails with
+    /// [`DnsError::Conflict`] if it is changed in between, see
+    /// [`retry_conflicts`](Self::retry_conflicts).
+    pub async fn add_iam_member(
+        &self,
+        managed_zone: &str,
+        role: &str,
+        member: &str,
+    ) -> Result<IamPolicy> {
+        self.modify_iam_policy(managed_zone, |policy| policy.add_member(role, member))
+            .await
+    }
+
+    /// Revokes `role` on the zone from `member` and returns the resulting
+    /// policy, see [`add_iam_member`](Self::add_iam_member).
+    pub async fn remove_iam_member(
+        &self,
+        managed_zone: &str,
+        role: &str,
+        member: &str,
+    ) -> Result<IamPolicy> {
+        self.modify_iam_policy(managed_zone, |policy| policy.remove_member(role, member))
+            .await
+    }
+
+    /// Applies `modify` to the current IAM policy and writes it back if it
+    /// returns `true`.
+    async fn modify_iam_policy<F>(&self, managed_zone: &str, modify: F) -> Result<IamPolicy>
+    where
+        F: Fn(&mut IamPolicy) -> bool,
+    {
+        let mut retries = 0;
+
+        loop {
+            let mut policy = self.get_iam_policy(managed_zone).await?;
+            if !modify(&mut policy) {
+                return Ok(policy);
+            }
+
+            match self.set_iam_policy(managed_zone, policy).await {
+                Err(DnsError::Dns { source })
+                    if matches!(
+                        source.kind(),
+                        ErrorKind::Conflict | ErrorKind::PreconditionFailed
+                    ) =>
+                {
+                    if retries >= self.conflict_retries {
+                        return Err(DnsError::Conflict { source });
+                    }
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
 }
@@ -2,23 +2,29 @@ use serde::{Deserialize, Serialize};
 
 pub mod changes;
 pub mod dns_keys;
+pub mod iam;
 pub mod managed_zone_operations;
 pub mod managed_zones;
 pub mod policies;
 pub mod projects;
 pub mod resource_record_sets;
+pub mod response_policies;
+pub mod response_policy_rules;
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ListEnvelope {
     pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub header: Option<Header>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub next_page_token: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateEnvelope {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub header: Option<Header>,
 }
 
@@ -1,42 +1,205 @@
+use std::net::IpAddr;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{DnsClient, Result};
+use crate::{
+    pagination::{with_query, ListResponse, ListStream, PageRequest},
+    DnsClient, Result,
+};
 
-use super::{ListEnvelope, UpdateEnvelope};
+use super::{managed_zones::ForwardingPath, ListEnvelope, UpdateEnvelope};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Configures name resolution for the VMs of the attached VPC networks.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Policy {
-    pub kind: String, // "dns#policy"
-    pub id: u64,
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#policy"
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub name: String,
-    pub enable_inbound_forwarding: bool,
-    pub description: String,
-    pub networks: PolicyNetwork,
-    pub alternative_name_server_config: AlternativeNameServerConfig,
-    pub enable_logging: bool,
+    /// Creates an inbound forwarder address in each attached network, which
+    /// resolves names from on-premises systems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_inbound_forwarding: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<PolicyNetwork>,
+    /// Forwards all queries from the attached networks to these name servers,
+    /// instead of using Cloud DNS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alternative_name_server_config: Option<AlternativeNameServerConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_logging: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns64_config: Option<Dns64Config>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Policy {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Attaches the policy to a VPC network, see [`Policy::attach_network`].
+    pub fn network(mut self, network_url: &str) -> Self {
+        self.attach_network(network_url);
+        self
+    }
+
+    pub fn enable_inbound_forwarding(mut self, enable_inbound_forwarding: bool) -> Self {
+        self.enable_inbound_forwarding = Some(enable_inbound_forwarding);
+        self
+    }
+
+    pub fn enable_logging(mut self, enable_logging: bool) -> Self {
+        self.enable_logging = Some(enable_logging);
+        self
+    }
+
+    /// Forwards all queries to `target_name_servers`.
+    pub fn alternative_name_servers<I>(mut self, target_name_servers: I) -> Self
+    where
+        I: IntoIterator<Item = AlternativeNameServerConfigTargetNameServers>,
+    {
+        self.alternative_name_server_config = Some(AlternativeNameServerConfig {
+            kind: None,
+            target_name_servers: target_name_servers.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Adds a VPC network, eg.
+    /// `https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default`
+    /// or `projects/my-project/global/networks/default`.
+    ///
+    /// Returns whether the policy changed, ie. `false` if the network was
+    /// already attached under either form of its URL.
+    pub fn attach_network(&mut self, network_url: &str) -> bool {
+        if self.has_network(network_url) {
+            return false;
+        }
+
+        self.networks.push(PolicyNetwork {
+            kind: None,
+            network_url: network_url.to_string(),
+        });
+        true
+    }
+
+    /// Removes a VPC network, returning whether it was attached.
+    pub fn detach_network(&mut self, network_url: &str) -> bool {
+        let len = self.networks.len();
+        self.networks
+            .retain(|network| !same_network(&network.network_url, network_url));
+
+        self.networks.len() != len
+    }
+
+    pub fn has_network(&self, network_url: &str) -> bool {
+        self.networks
+            .iter()
+            .any(|network| same_network(&network.network_url, network_url))
+    }
+}
+
+/// Compares network URLs, which the API accepts both with and without the
+/// Compute Engine API prefix.
+fn same_network(a: &str, b: &str) -> bool {
+    fn resource_name(url: &str) -> &str {
+        const PREFIXES: [&str; 2] = [
+            "https://www.googleapis.com/compute/v1/",
+            "https://compute.googleapis.com/compute/v1/",
+        ];
+
+        PREFIXES
+            .iter()
+            .find_map(|prefix| url.strip_prefix(prefix))
+            .unwrap_or(url)
+    }
+
+    resource_name(a) == resource_name(b)
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PolicyNetwork {
-    pub kind: String, // "dns#policyNetwork"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#policyNetwork"
     pub network_url: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AlternativeNameServerConfig {
-    pub kind: String, // "dns#policyAlternativeNameServerConfig"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#policyAlternativeNameServerConfig"
+    #[serde(default)]
     pub target_name_servers: Vec<AlternativeNameServerConfigTargetNameServers>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AlternativeNameServerConfigTargetNameServers {
-    pub kind: String, // "dns#policyAlternativeNameServerConfigTargetNameServer"
-    pub ipv4_address: String,
-    pub forwarding_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#policyAlternativeNameServerConfigTargetNameServer"
+    /// Exactly one of `ipv4_address` and `ipv6_address` must be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv4_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarding_path: Option<ForwardingPath>,
+}
+
+impl AlternativeNameServerConfigTargetNameServers {
+    /// A name server at `address`, forwarded to according to its address.
+    pub fn new(address: IpAddr) -> Self {
+        let (ipv4_address, ipv6_address) = match address {
+            IpAddr::V4(address) => (Some(address.to_string()), None),
+            IpAddr::V6(address) => (None, Some(address.to_string())),
+        };
+
+        Self {
+            kind: None,
+            ipv4_address,
+            ipv6_address,
+            forwarding_path: None,
+        }
+    }
+
+    pub fn forwarding_path(mut self, forwarding_path: ForwardingPath) -> Self {
+        self.forwarding_path = Some(forwarding_path);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Dns64Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#policyDns64Config"
+    pub scope: Dns64ConfigScope,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Dns64ConfigScope {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#policyDns64ConfigScope"
+    /// Synthesizes IPv6 addresses for all IPv4-only names.
+    #[serde(default)]
+    pub all_queries: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -44,9 +207,22 @@ pub struct AlternativeNameServerConfigTargetNameServers {
 pub struct Policies {
     #[serde(flatten)]
     pub envelope: ListEnvelope,
+    #[serde(default)]
     pub policies: Vec<Policy>,
 }
 
+impl ListResponse for Policies {
+    type Item = Policy;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.envelope.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Policy> {
+        self.policies
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdatePolicy {
@@ -55,6 +231,9 @@ pub struct UpdatePolicy {
     pub policy: Policy,
 }
 
+/// The query parameters of a `list` call on [`PoliciesHandler`].
+pub type ListPoliciesRequest = PageRequest;
+
 pub struct PoliciesHandler<'client> {
     client: &'client DnsClient,
 }
@@ -64,19 +243,24 @@ impl<'client> PoliciesHandler<'client> {
         Self { client }
     }
 
-    pub async fn list(&self) -> Result<Policies> {
-        let route = "policies".to_string();
+    pub async fn list(&self, request: &ListPoliciesRequest) -> Result<Policies> {
+        let route = with_query("policies".to_string(), request);
 
         self.client.get(route).await
     }
 
+    /// Streams all policies in the project, fetching further pages as needed.
+    pub fn list_all(&self, request: ListPoliciesRequest) -> ListStream<'client, Policy> {
+        ListStream::new::<Policies, _>(self.client, "policies".to_string(), request)
+    }
+
     pub async fn get(&self, policy: &str) -> Result<Policy> {
         let route = format!("policies/{policy}", policy = policy,);
 
         self.client.get(route).await
     }
 
-    pub async fn patch(&self, policy_id: &str, policy: Policy) -> Result<Policy> {
+    pub async fn patch(&self, policy_id: &str, policy: Policy) -> Result<UpdatePolicy> {
         let route = format!("policies/{policy_id}", policy_id = policy_id,);
 
         self.client.patch(route, Some(&policy)).await
@@ -99,4 +283,40 @@ impl<'client> PoliciesHandler<'client> {
 
         self.client.put(route, Some(&policy)).await
     }
+
+    /// Attaches the policy to a VPC network, see [`Policy::attach_network`],
+    /// and returns the updated policy.
+    ///
+    /// The policy is read and patched with all its networks. Policies have no
+    /// etag, so a concurrent change of the networks may be overwritten.
+    pub async fn attach_network(&self, policy_id: &str, network_url: &str) -> Result<Policy> {
+        self.modify_networks(policy_id, |policy| policy.attach_network(network_url))
+            .await
+    }
+
+    /// Detaches the policy from a VPC network and returns the updated policy,
+    /// see [`attach_network`](Self::attach_network).
+    pub async fn detach_network(&self, policy_id: &str, network_url: &str) -> Result<Policy> {
+        self.modify_networks(policy_id, |policy| policy.detach_network(network_url))
+            .await
+    }
+
+    async fn modify_networks<F>(&self, policy_id: &str, modify: F) -> Result<Policy>
+    where
+        F: FnOnce(&mut Policy) -> bool,
+    {
+        let mut policy = self.get(policy_id).await?;
+        if !modify(&mut policy) {
+            return Ok(policy);
+        }
+
+        // Only the networks are sent, leaving the other fields as they are.
+        // They are sent even if empty, so that detaching the last network
+        // clears the list instead of leaving it unchanged.
+        let route = format!("policies/{policy_id}", policy_id = policy_id,);
+        let body = serde_json::json!({ "networks": policy.networks });
+        let updated: UpdatePolicy = self.client.patch(route, Some(&body)).await?;
+
+        Ok(updated.policy)
+    }
 }
@@ -2,16 +2,16 @@ use serde::{Deserialize, Serialize};
 
 use crate::{DnsClient, Result};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Project {
     pub kind: String, // "dns#project"
-    pub number: u64,
+    pub number: String,
     pub id: String,
     pub quota: Quota,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Quota {
     pub kind: String, // "dns#quota"
@@ -29,9 +29,31 @@ pub struct Quota {
     pub networks_per_policy: i64,
     pub target_name_servers_per_policy: i64,
     pub target_name_servers_per_managed_zone: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peering_zones_per_target_network: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gke_clusters_per_managed_zone: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gke_clusters_per_policy: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gke_clusters_per_response_policy: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub managed_zones_per_gke_cluster: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_policies: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub networks_per_response_policy: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_policy_rules_per_response_policy: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items_per_routing_policy: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nameservers_per_delegation: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internet_health_checks_per_managed_zone: Option<i64>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct WhitelistedKeySpec {
     pub kind: String, // "dns#dnsKeySpec"
@@ -50,6 +72,14 @@ impl<'client> ProjectsHandler<'client> {
     }
 
     pub async fn get(&self) -> Result<Project> {
-        self.client.get("/").await
+        // The project is the base URL itself, without its trailing slash.
+        let url = self
+            .client
+            .base_url
+            .as_str()
+            .trim_end_matches('/')
+            .to_string();
+
+        self.client.get(url).await
     }
 }
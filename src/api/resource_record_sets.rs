@@ -1,50 +1,467 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{DnsClient, Result};
+use crate::{
+    error::{DnsError, ErrorKind},
+    pagination::{
+        page_request_methods, with_query, ListRequest, ListResponse, ListStream, PageRequest,
+    },
+    rdata::{RData, RDataError, RecordType},
+    zone_file, DnsClient, Result,
+};
 
-use super::ListEnvelope;
+use super::{changes::Change, ListEnvelope};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceRecordSet {
-    pub kind: String, // "dns#resourceRecordSet"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#resourceRecordSet"
     pub name: String,
-    pub r#type: String,
-    pub ttl: i32,
+    pub r#type: RecordType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rrdatas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature_rrdatas: Vec<String>,
+    /// Answers queries with different rrdatas depending on the location of the
+    /// client, weights or health checks, instead of `rrdatas`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_policy: Option<RRSetRoutingPolicy>,
+}
+
+impl ResourceRecordSet {
+    /// A record set of `type`, eg. `A` or `TXT`, at the fully qualified
+    /// `name`, eg. `www.example.com.`.
+    pub fn new<I, S>(name: &str, r#type: RecordType, ttl: i32, rrdatas: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            kind: None,
+            name: name.to_string(),
+            r#type,
+            ttl: Some(ttl),
+            rrdatas: rrdatas.into_iter().map(Into::into).collect(),
+            signature_rrdatas: Vec::new(),
+            routing_policy: None,
+        }
+    }
+
+    /// A record set whose rrdatas are chosen by `routing_policy`, see
+    /// [`RRSetRoutingPolicy`].
+    pub fn routed(
+        name: &str,
+        r#type: RecordType,
+        ttl: i32,
+        routing_policy: RRSetRoutingPolicy,
+    ) -> Self {
+        Self {
+            routing_policy: Some(routing_policy),
+            ..Self::new(name, r#type, ttl, Vec::<String>::new())
+        }
+    }
+
+    /// A record set with typed record data, see [`RData`].
+    pub fn from_rdata<I>(name: &str, r#type: RecordType, ttl: i32, rdatas: I) -> Self
+    where
+        I: IntoIterator<Item = RData>,
+    {
+        Self::new(name, r#type, ttl, rdatas.into_iter().map(|r| r.to_string()))
+    }
+
+    /// Parses `rrdatas` according to the type of the record set.
+    pub fn typed_rrdatas(&self) -> std::result::Result<Vec<RData>, RDataError> {
+        self.rrdatas
+            .iter()
+            .map(|rrdata| RData::parse(self.r#type.clone(), rrdata))
+            .collect()
+    }
+}
+
+/// Chooses the rrdatas of a record set per query. Exactly one of `geo`, `wrr`
+/// and `primary_backup` must be set.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RRSetRoutingPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicy"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<GeoPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrr: Option<WrrPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_backup: Option<PrimaryBackupPolicy>,
+    /// The health check for the `external_endpoints` of the policy's
+    /// targets, eg. `projects/my-project/global/healthChecks/web`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<String>,
+}
+
+impl RRSetRoutingPolicy {
+    /// Answers with the item closest to the client's location.
+    pub fn geo<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = GeoPolicyItem>,
+    {
+        Self {
+            geo: Some(GeoPolicy {
+                items: items.into_iter().collect(),
+                ..GeoPolicy::default()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Answers with an item picked at random in proportion to its weight.
+    pub fn wrr<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = WrrPolicyItem>,
+    {
+        Self {
+            wrr: Some(WrrPolicy {
+                kind: None,
+                items: items.into_iter().collect(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Answers with the healthy `primary_targets`, or with `backup_geo_targets`
+    /// once none of them are healthy.
+    pub fn primary_backup(
+        primary_targets: HealthCheckTargets,
+        backup_geo_targets: GeoPolicy,
+    ) -> Self {
+        Self {
+            primary_backup: Some(PrimaryBackupPolicy {
+                kind: None,
+                primary_targets,
+                backup_geo_targets,
+                trickle_traffic: None,
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicyGeoPolicy"
+    #[serde(default)]
+    pub items: Vec<GeoPolicyItem>,
+    /// Only answers with the items in the client's own location while any of
+    /// them is healthy, instead of failing over to the next closest location.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub enable_fencing: bool,
+}
+
+impl GeoPolicy {
+    pub fn enable_fencing(mut self, enable_fencing: bool) -> Self {
+        self.enable_fencing = enable_fencing;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoPolicyItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicyGeoPolicyGeoPolicyItem"
+    /// A Google Cloud region, eg. `us-east1`.
+    pub location: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub rrdatas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub signature_rrdatas: Vec<String>,
+    /// The targets whose health decides whether the item is answered with,
+    /// instead of `rrdatas`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_checked_targets: Option<HealthCheckTargets>,
+}
+
+impl GeoPolicyItem {
+    pub fn new<I, S>(location: &str, rrdatas: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            location: location.to_string(),
+            rrdatas: rrdatas.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn health_checked(location: &str, targets: HealthCheckTargets) -> Self {
+        Self {
+            location: location.to_string(),
+            health_checked_targets: Some(targets),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WrrPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicyWrrPolicy"
+    #[serde(default)]
+    pub items: Vec<WrrPolicyItem>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WrrPolicyItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicyWrrPolicyWrrPolicyItem"
+    /// The relative weight of the item, which must not be negative.
+    pub weight: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rrdatas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature_rrdatas: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_checked_targets: Option<HealthCheckTargets>,
+}
+
+impl WrrPolicyItem {
+    pub fn new<I, S>(weight: f64, rrdatas: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            weight,
+            rrdatas: rrdatas.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn health_checked(weight: f64, targets: HealthCheckTargets) -> Self {
+        Self {
+            weight,
+            health_checked_targets: Some(targets),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrimaryBackupPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicyPrimaryBackupPolicy"
+    pub primary_targets: HealthCheckTargets,
+    pub backup_geo_targets: GeoPolicy,
+    /// The fraction of traffic, between 0 and 1, sent to the backup targets
+    /// even while the primary targets are healthy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trickle_traffic: Option<f64>,
+}
+
+impl PrimaryBackupPolicy {
+    pub fn trickle_traffic(mut self, trickle_traffic: f64) -> Self {
+        self.trickle_traffic = Some(trickle_traffic);
+        self
+    }
+}
+
+/// The targets of a routing policy item whose health is checked. The item is
+/// answered with the IP addresses of the healthy targets.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckTargets {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub internal_load_balancers: Vec<LoadBalancerTarget>,
+    /// IP addresses checked by the policy's `health_check`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_endpoints: Vec<String>,
+}
+
+impl HealthCheckTargets {
+    pub fn internal_load_balancers<I>(targets: I) -> Self
+    where
+        I: IntoIterator<Item = LoadBalancerTarget>,
+    {
+        Self {
+            internal_load_balancers: targets.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn external_endpoints<I, S>(endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            external_endpoints: endpoints.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
 }
+
+/// The forwarding rule of an internal load balancer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadBalancerTarget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#rRSetRoutingPolicyLoadBalancerTarget"
+    pub load_balancer_type: LoadBalancerType,
+    pub ip_address: String,
+    pub port: String,
+    pub ip_protocol: IpProtocol,
+    /// The VPC network of the load balancer, eg.
+    /// `https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default`.
+    pub network_url: String,
+    pub project: String,
+    /// Not set for global load balancers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LoadBalancerType {
+    None,
+    GlobalL7ilb,
+    RegionalL4ilb,
+    RegionalL7ilb,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpProtocol {
+    Undefined,
+    Tcp,
+    Udp,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceRecordSets {
     #[serde(flatten)]
     pub envelope: ListEnvelope,
+    #[serde(default)]
     pub rrsets: Vec<ResourceRecordSet>,
 }
 
+impl ListResponse for ResourceRecordSets {
+    type Item = ResourceRecordSet;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.envelope.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<ResourceRecordSet> {
+        self.rrsets
+    }
+}
+
+/// The query parameters of a `list` call on [`ResourceRecordSetsHandler`].
+#[derive(Debug, Clone, Default)]
+pub struct ListRecordSetsRequest {
+    page: PageRequest,
+    name: Option<String>,
+    r#type: Option<RecordType>,
+}
+
+impl ListRecordSetsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    page_request_methods!();
+
+    /// Only returns record sets with the given fully qualified name, eg.
+    /// `www.example.com.`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only returns record sets of the given type, eg. `A`. Requires
+    /// [`name`](Self::name) to be set.
+    pub fn r#type(mut self, r#type: RecordType) -> Self {
+        self.r#type = Some(r#type);
+        self
+    }
+}
+
+impl ListRequest for ListRecordSetsRequest {
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = self.page.query_pairs();
+
+        if let Some(name) = &self.name {
+            pairs.push(("name", name.clone()));
+        }
+        if let Some(r#type) = &self.r#type {
+            pairs.push(("type", r#type.to_string()));
+        }
+
+        pairs
+    }
+
+    fn set_page_token(&mut self, page_token: Option<String>) {
+        self.page.set_page_token(page_token);
+    }
+}
+
 pub struct ResourceRecordSetsHandler<'client> {
     client: &'client DnsClient,
+    conflict_retries: u32,
 }
 
 impl<'client> ResourceRecordSetsHandler<'client> {
     pub(crate) fn new(client: &'client DnsClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            conflict_retries: 0,
+        }
     }
 
-    pub async fn list(&self, managed_zone: &str) -> Result<ResourceRecordSets> {
-        let route = format!(
-            "managedZones/{managed_zone}/rrsets",
-            managed_zone = managed_zone,
-        );
+    /// Retries [`upsert`](Self::upsert) and [`replace_many`](Self::replace_many)
+    /// up to `retries` times when the record sets are changed concurrently,
+    /// re-reading their current state each time. They are not retried by
+    /// default.
+    pub fn retry_conflicts(mut self, retries: u32) -> Self {
+        self.conflict_retries = retries;
+        self
+    }
+
+    pub async fn list(
+        &self,
+        managed_zone: &str,
+        request: &ListRecordSetsRequest,
+    ) -> Result<ResourceRecordSets> {
+        let route = with_query(format!("managedZones/{}/rrsets", managed_zone), request);
 
         self.client.get(route).await
     }
 
+    /// Streams all record sets in the zone, fetching further pages as needed.
+    pub fn list_all(
+        &self,
+        managed_zone: &str,
+        request: ListRecordSetsRequest,
+    ) -> ListStream<'client, ResourceRecordSet> {
+        ListStream::new::<ResourceRecordSets, _>(
+            self.client,
+            format!("managedZones/{}/rrsets", managed_zone),
+            request,
+        )
+    }
+
     pub async fn get(
         &self,
         managed_zone: &str,
         name: &str,
-        r#type: &str,
+        r#type: RecordType,
     ) -> Result<ResourceRecordSet> {
         let route = format!(
             "managedZones/{managed_zone}/rrsets/{name}/{typ}",
@@ -60,7 +477,7 @@ impl<'client> ResourceRecordSetsHandler<'client> {
         &self,
         managed_zone: &str,
         name: &str,
-        r#type: &str,
+        r#type: RecordType,
         record_set: ResourceRecordSet,
     ) -> Result<ResourceRecordSet> {
         let route = format!(
@@ -86,7 +503,7 @@ impl<'client> ResourceRecordSetsHandler<'client> {
         self.client.post(route, Some(&record_set)).await
     }
 
-    pub async fn delete(&self, managed_zone: &str, name: &str, r#type: &str) -> Result<()> {
+    pub async fn delete(&self, managed_zone: &str, name: &str, r#type: RecordType) -> Result<()> {
         let route = format!(
             "managedZones/{managed_zone}/rrsets/{name}/{typ}",
             managed_zone = managed_zone,
@@ -96,4 +513,86 @@ impl<'client> ResourceRecordSetsHandler<'client> {
 
         self.client.delete(route).await
     }
+
+    /// Exports all record sets of the zone as a BIND zone file, see
+    /// [`zone_file::render`].
+    pub async fn export_zone_file(&self, managed_zone: &str) -> Result<String> {
+        let zone = self
+            .client
+            .managed_zones()
+            .get(managed_zone.to_string())
+            .await?;
+        let rrsets = self
+            .list_all(managed_zone, Default::default())
+            .collect_all()
+            .await?;
+
+        Ok(zone_file::render(&zone.dns_name, &rrsets))
+    }
+
+    /// Creates the record set, or atomically replaces the existing one with
+    /// the same name and type, see [`replace_many`](Self::replace_many).
+    pub async fn upsert(
+        &self,
+        managed_zone: &str,
+        record_set: ResourceRecordSet,
+    ) -> Result<Change> {
+        self.replace_many(managed_zone, vec![record_set]).await
+    }
+
+    /// Creates the record sets, or replaces the existing ones with the same
+    /// names and types, in a single change.
+    ///
+    /// The change deletes the record sets exactly as they currently are, so it
+    /// fails with [`DnsError::Conflict`] if any of them is changed before it is
+    /// applied, see [`retry_conflicts`](Self::retry_conflicts).
+    pub async fn replace_many(
+        &self,
+        managed_zone: &str,
+        record_sets: Vec<ResourceRecordSet>,
+    ) -> Result<Change> {
+        let mut retries = 0;
+
+        loop {
+            let deletions = self.current(managed_zone, &record_sets).await?;
+            let change = Change::new(record_sets.clone(), deletions);
+
+            match self.client.changes().create(managed_zone, change).await {
+                Err(DnsError::Dns { source })
+                    if matches!(
+                        source.kind(),
+                        ErrorKind::Conflict | ErrorKind::PreconditionFailed
+                    ) =>
+                {
+                    if retries >= self.conflict_retries {
+                        return Err(DnsError::Conflict { source });
+                    }
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// The current state of those `record_sets` that exist.
+    async fn current(
+        &self,
+        managed_zone: &str,
+        record_sets: &[ResourceRecordSet],
+    ) -> Result<Vec<ResourceRecordSet>> {
+        let current =
+            futures::future::try_join_all(record_sets.iter().map(|record_set| async move {
+                match self
+                    .get(managed_zone, &record_set.name, record_set.r#type.clone())
+                    .await
+                {
+                    Ok(current) => Ok(Some(current)),
+                    Err(e) if e.is_not_found() => Ok(None),
+                    Err(e) => Err(e),
+                }
+            }))
+            .await?;
+
+        Ok(current.into_iter().flatten().collect())
+    }
 }
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pagination::{with_query, ListResponse, ListStream, PageRequest},
+    DnsClient, Result,
+};
+
+use super::{Header, UpdateEnvelope};
+
+/// A set of rules that override the answers to queries from the attached
+/// networks and GKE clusters, see
+/// [`ResponsePolicyRulesHandler`](super::response_policy_rules::ResponsePolicyRulesHandler).
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#responsePolicy"
+    /// Output only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub response_policy_name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<ResponsePolicyNetwork>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gke_clusters: Vec<ResponsePolicyGkeCluster>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
+
+impl ResponsePolicy {
+    pub fn new(response_policy_name: &str) -> Self {
+        Self {
+            response_policy_name: response_policy_name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Applies the policy to a VPC network, eg.
+    /// `https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default`.
+    pub fn network(mut self, network_url: &str) -> Self {
+        self.networks.push(ResponsePolicyNetwork {
+            kind: None,
+            network_url: network_url.to_string(),
+        });
+        self
+    }
+
+    /// Applies the policy to a GKE cluster, eg.
+    /// `projects/my-project/locations/us-central1/clusters/my-cluster`.
+    pub fn gke_cluster(mut self, gke_cluster_name: &str) -> Self {
+        self.gke_clusters.push(ResponsePolicyGkeCluster {
+            kind: None,
+            gke_cluster_name: gke_cluster_name.to_string(),
+        });
+        self
+    }
+
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicyNetwork {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#responsePolicyNetwork"
+    pub network_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicyGkeCluster {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#responsePolicyGKECluster"
+    pub gke_cluster_name: String,
+}
+
+/// Unlike the other list responses, this one has no `kind`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicies {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<Header>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(default)]
+    pub response_policies: Vec<ResponsePolicy>,
+}
+
+impl ListResponse for ResponsePolicies {
+    type Item = ResponsePolicy;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<ResponsePolicy> {
+        self.response_policies
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateResponsePolicy {
+    #[serde(flatten)]
+    pub envelope: UpdateEnvelope,
+    pub response_policy: ResponsePolicy,
+}
+
+/// The query parameters of a `list` call on [`ResponsePoliciesHandler`].
+pub type ListResponsePoliciesRequest = PageRequest;
+
+pub struct ResponsePoliciesHandler<'client> {
+    client: &'client DnsClient,
+}
+
+impl<'client> ResponsePoliciesHandler<'client> {
+    pub(crate) fn new(client: &'client DnsClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn list(&self, request: &ListResponsePoliciesRequest) -> Result<ResponsePolicies> {
+        let route = with_query("responsePolicies".to_string(), request);
+
+        self.client.get(route).await
+    }
+
+    /// Streams all response policies in the project, fetching further pages as
+    /// needed.
+    pub fn list_all(
+        &self,
+        request: ListResponsePoliciesRequest,
+    ) -> ListStream<'client, ResponsePolicy> {
+        ListStream::new::<ResponsePolicies, _>(self.client, "responsePolicies".to_string(), request)
+    }
+
+    pub async fn get(&self, response_policy: &str) -> Result<ResponsePolicy> {
+        let route = format!(
+            "responsePolicies/{response_policy}",
+            response_policy = response_policy,
+        );
+
+        self.client.get(route).await
+    }
+
+    pub async fn patch(
+        &self,
+        response_policy: &str,
+        policy: ResponsePolicy,
+    ) -> Result<UpdateResponsePolicy> {
+        let route = format!(
+            "responsePolicies/{response_policy}",
+            response_policy = response_policy,
+        );
+
+        self.client.patch(route, Some(&policy)).await
+    }
+
+    pub async fn create(&self, policy: ResponsePolicy) -> Result<ResponsePolicy> {
+        let route = "responsePolicies".to_string();
+
+        self.client.post(route, Some(&policy)).await
+    }
+
+    /// Deletes the response policy, which must not have any rules or be
+    /// attached to any networks or GKE clusters.
+    pub async fn delete(&self, response_policy: &str) -> Result<()> {
+        let route = format!(
+            "responsePolicies/{response_policy}",
+            response_policy = response_policy,
+        );
+
+        self.client.delete(route).await
+    }
+
+    pub async fn update(
+        &self,
+        response_policy: &str,
+        policy: ResponsePolicy,
+    ) -> Result<UpdateResponsePolicy> {
+        let route = format!(
+            "responsePolicies/{response_policy}",
+            response_policy = response_policy,
+        );
+
+        self.client.put(route, Some(&policy)).await
+    }
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pagination::{with_query, ListResponse, ListStream, PageRequest},
+    DnsClient, Result,
+};
+
+use super::{resource_record_sets::ResourceRecordSet, Header, UpdateEnvelope};
+
+/// Overrides the answers to queries for `dns_name`, and for the names below
+/// it if `dns_name` is a wildcard such as `*.example.com.`.
+///
+/// A rule either answers with its `local_data`, or sets `behavior` to exempt
+/// the name from a wider rule of the same policy.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicyRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>, // "dns#responsePolicyRule"
+    pub rule_name: String,
+    pub dns_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behavior: Option<Behavior>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_data: Option<LocalData>,
+}
+
+impl ResponsePolicyRule {
+    /// A rule answering queries for `dns_name` with `record_sets` instead of
+    /// the usual response, eg. to point a public name at a private address.
+    pub fn local_data<I>(rule_name: &str, dns_name: &str, record_sets: I) -> Self
+    where
+        I: IntoIterator<Item = ResourceRecordSet>,
+    {
+        Self {
+            rule_name: rule_name.to_string(),
+            dns_name: dns_name.to_string(),
+            local_data: Some(LocalData {
+                local_datas: record_sets.into_iter().collect(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// A rule resolving `dns_name` normally, even though a wildcard rule of the
+    /// same policy matches it.
+    pub fn bypass(rule_name: &str, dns_name: &str) -> Self {
+        Self {
+            rule_name: rule_name.to_string(),
+            dns_name: dns_name.to_string(),
+            behavior: Some(Behavior::BypassResponsePolicy),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Behavior {
+    BehaviorUnspecified,
+    BypassResponsePolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalData {
+    #[serde(default)]
+    pub local_datas: Vec<ResourceRecordSet>,
+}
+
+/// Unlike the other list responses, this one has no `kind`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicyRules {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<Header>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(default)]
+    pub response_policy_rules: Vec<ResponsePolicyRule>,
+}
+
+impl ListResponse for ResponsePolicyRules {
+    type Item = ResponsePolicyRule;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<ResponsePolicyRule> {
+        self.response_policy_rules
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateResponsePolicyRule {
+    #[serde(flatten)]
+    pub envelope: UpdateEnvelope,
+    pub response_policy_rule: ResponsePolicyRule,
+}
+
+/// The query parameters of a `list` call on [`ResponsePolicyRulesHandler`].
+pub type ListResponsePolicyRulesRequest = PageRequest;
+
+pub struct ResponsePolicyRulesHandler<'client> {
+    client: &'client DnsClient,
+}
+
+impl<'client> ResponsePolicyRulesHandler<'client> {
+    pub(crate) fn new(client: &'client DnsClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn list(
+        &self,
+        response_policy: &str,
+        request: &ListResponsePolicyRulesRequest,
+    ) -> Result<ResponsePolicyRules> {
+        let route = with_query(
+            format!("responsePolicies/{}/rules", response_policy),
+            request,
+        );
+
+        self.client.get(route).await
+    }
+
+    /// Streams all rules of the response policy, fetching further pages as
+    /// needed.
+    pub fn list_all(
+        &self,
+        response_policy: &str,
+        request: ListResponsePolicyRulesRequest,
+    ) -> ListStream<'client, ResponsePolicyRule> {
+        ListStream::new::<ResponsePolicyRules, _>(
+            self.client,
+            format!("responsePolicies/{}/rules", response_policy),
+            request,
+        )
+    }
+
+    pub async fn get(&self, response_policy: &str, rule: &str) -> Result<ResponsePolicyRule> {
+        let route = format!(
+            "responsePolicies/{response_policy}/rules/{rule}",
+            response_policy = response_policy,
+            rule = rule,
+        );
+
+        self.client.get(route).await
+    }
+
+    pub async fn patch(
+        &self,
+        response_policy: &str,
+        rule: &str,
+        response_policy_rule: ResponsePolicyRule,
+    ) -> Result<UpdateResponsePolicyRule> {
+        let route = format!(
+            "responsePolicies/{response_policy}/rules/{rule}",
+            response_policy = response_policy,
+            rule = rule,
+        );
+
+        self.client.patch(route, Some(&response_policy_rule)).await
+    }
+
+    pub async fn create(
+        &self,
+        response_policy: &str,
+        response_policy_rule: ResponsePolicyRule,
+    ) -> Result<ResponsePolicyRule> {
+        let route = format!(
+            "responsePolicies/{response_policy}/rules",
+            response_policy = response_policy,
+        );
+
+        self.client.post(route, Some(&response_policy_rule)).await
+    }
+
+    pub async fn delete(&self, response_policy: &str, rule: &str) -> Result<()> {
+        let route = format!(
+            "responsePolicies/{response_policy}/rules/{rule}",
+            response_policy = response_policy,
+            rule = rule,
+        );
+
+        self.client.delete(route).await
+    }
+
+    pub async fn update(
+        &self,
+        response_policy: &str,
+        rule: &str,
+        response_policy_rule: ResponsePolicyRule,
+    ) -> Result<UpdateResponsePolicyRule> {
+        let route = format!(
+            "responsePolicies/{response_policy}/rules/{rule}",
+            response_policy = response_policy,
+            rule = rule,
+        );
+
+        self.client.put(route, Some(&response_policy_rule)).await
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    any::Any,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use tame_oauth::{
+    gcp::{
+        ServiceAccountInfo, ServiceAccountProvider, TokenOrRequest, TokenProvider,
+        TokenProviderWrapper,
+    },
+    Token,
+};
+
+use crate::{error::DnsError, Result};
+
+/// Full read/write access to Cloud DNS, used by default.
+pub const READ_WRITE_SCOPE: &str = "https://www.googleapis.com/auth/ndev.clouddns.readwrite";
+/// Read only access to Cloud DNS.
+pub const READ_ONLY_SCOPE: &str = "https://www.googleapis.com/auth/ndev.clouddns.readonly";
+/// Full access to all Google Cloud services.
+pub const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// An object safe version of [`TokenProvider`], so that any provider can be
+/// stored in a [`DnsClient`](crate::DnsClient).
+pub(crate) trait DynTokenProvider: Send + Sync {
+    fn get_token(&self, scopes: &[String]) -> Result<TokenOrRequest, tame_oauth::Error>;
+
+    fn parse_token_response(
+        &self,
+        scope_hash: u64,
+        response: http::Response<bytes::Bytes>,
+    ) -> Result<Token, tame_oauth::Error>;
+}
+
+impl<P> DynTokenProvider for P
+where
+    P: TokenProvider + Send + Sync,
+{
+    fn get_token(&self, scopes: &[String]) -> Result<TokenOrRequest, tame_oauth::Error> {
+        TokenProvider::get_token(self, scopes)
+    }
+
+    fn parse_token_response(
+        &self,
+        scope_hash: u64,
+        response: http::Response<bytes::Bytes>,
+    ) -> Result<Token, tame_oauth::Error> {
+        TokenProvider::parse_token_response(self, scope_hash, response)
+    }
+}
+
+/// Prepares a provider to be stored in an [`Authenticator`].
+///
+/// A [`ServiceAccountProvider`] hands out its own cached token until that has
+/// actually expired, so refreshing ahead of expiry would get the same token
+/// back. Service accounts are therefore replaced by a provider that always
+/// requests a new token, and only the [`TokenCache`] caches them.
+pub(crate) fn dyn_provider<P>(provider: P) -> Arc<dyn DynTokenProvider>
+where
+    P: TokenProvider + Send + Sync + 'static,
+{
+    let provider: Box<dyn Any> = Box::new(provider);
+
+    let provider = match provider.downcast::<TokenProviderWrapper>() {
+        Ok(wrapper) => match *wrapper {
+            TokenProviderWrapper::ServiceAccount(provider) => {
+                return Arc::new(UncachedServiceAccount(provider.get_account_info().clone()))
+            }
+            wrapper => return Arc::new(wrapper),
+        },
+        Err(provider) => provider,
+    };
+
+    match provider.downcast::<ServiceAccountProvider>() {
+        Ok(provider) => Arc::new(UncachedServiceAccount(provider.get_account_info().clone())),
+        Err(provider) => Arc::new(*provider.downcast::<P>().expect("the provider is a `P`")),
+    }
+}
+
+/// A service account provider without a token cache, see [`dyn_provider`].
+struct UncachedServiceAccount(ServiceAccountInfo);
+
+impl UncachedServiceAccount {
+    /// A new provider, whose cache is empty.
+    fn provider(&self) -> Result<ServiceAccountProvider, tame_oauth::Error> {
+        ServiceAccountProvider::new(self.0.clone())
+    }
+}
+
+impl DynTokenProvider for UncachedServiceAccount {
+    fn get_token(&self, scopes: &[String]) -> Result<TokenOrRequest, tame_oauth::Error> {
+        TokenProvider::get_token(&self.provider()?, scopes)
+    }
+
+    fn parse_token_response(
+        &self,
+        scope_hash: u64,
+        response: http::Response<bytes::Bytes>,
+    ) -> Result<Token, tame_oauth::Error> {
+        TokenProvider::parse_token_response(&self.provider()?, scope_hash, response)
+    }
+}
+
+/// Tokens are treated as expired this long before they actually expire, so
+/// they don't expire while a request is in flight.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+/// Tokens are refreshed in the background once they are this close to expiry.
+const REFRESH_AHEAD: Duration = Duration::from_secs(5 * 60);
+/// A background refresh is started at most this often, so that a failing or
+/// short-lived refresh doesn't start a new one on every request.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Holds the token provider and scopes used to authenticate requests.
+pub(crate) struct Authenticator {
+    /// Resolved lazily from the environment if not supplied by the user.
+    provider: Mutex<Option<Arc<dyn DynTokenProvider>>>,
+    pub(crate) scopes: Vec<String>,
+    pub(crate) cache: TokenCache,
+}
+
+impl Authenticator {
+    pub(crate) fn new(provider: Option<Arc<dyn DynTokenProvider>>, scopes: Vec<String>) -> Self {
+        Self {
+            provider: Mutex::new(provider),
+            scopes,
+            cache: TokenCache::default(),
+        }
+    }
+
+    /// Returns the configured provider, falling back to the
+    /// [default provider](TokenProviderWrapper::get_default_provider).
+    pub(crate) fn provider(&self) -> Result<Arc<dyn DynTokenProvider>> {
+        let mut provider = self
+            .provider
+            .lock()
+            .map_err(|_| DnsError::Auth(tame_oauth::Error::Poisoned))?;
+
+        if let Some(provider) = provider.as_ref() {
+            return Ok(provider.clone());
+        }
+
+        let default = dyn_provider(
+            TokenProviderWrapper::get_default_provider()?.ok_or(DnsError::MissingCredentials)?,
+        );
+        *provider = Some(default.clone());
+
+        Ok(default)
+    }
+}
+
+/// A cache of the most recently fetched token, shared by all clones of a
+/// [`DnsClient`](crate::DnsClient).
+#[derive(Default)]
+pub(crate) struct TokenCache {
+    token: RwLock<Option<Token>>,
+    /// Held while a token is being fetched, so that concurrent requests wait
+    /// for a single refresh rather than each fetching their own token.
+    refresh: Arc<tokio::sync::Mutex<()>>,
+    /// When the last background refresh was started.
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+impl TokenCache {
+    /// Returns the cached token, unless it is about to expire. Tokens without
+    /// an expiry are kept until replaced.
+    pub(crate) fn get(&self) -> Option<Token> {
+        let token = self.token.read().ok()?;
+
+        token
+            .as_ref()
+            .filter(|token| expires_within(token, EXPIRY_MARGIN) != Some(true))
+            .cloned()
+    }
+
+    pub(crate) fn set(&self, token: Token) {
+        if let Ok(mut cached) = self.token.write() {
+            *cached = Some(token);
+        }
+    }
+
+    /// Whether the token is close enough to expiry that it should be refreshed
+    /// ahead of time.
+    pub(crate) fn should_refresh(&self, token: &Token) -> bool {
+        expires_within(token, REFRESH_AHEAD) == Some(true)
+    }
+
+    /// Waits for any in flight refresh to finish, the returned guard must be
+    /// held while fetching a new token.
+    pub(crate) async fn lock(&self) -> tokio::sync::OwnedMutexGuard<()> {
+        self.refresh.clone().lock_owned().await
+    }
+
+    /// Like [`TokenCache::lock`] for a background refresh, but returns `None`
+    /// if a refresh is already in flight or one was started less than
+    /// [`REFRESH_INTERVAL`] ago.
+    pub(crate) fn try_start_refresh(&self) -> Option<tokio::sync::OwnedMutexGuard<()>> {
+        let mut last_refresh = self.last_refresh.lock().ok()?;
+        if matches!(*last_refresh, Some(last) if last.elapsed() < REFRESH_INTERVAL) {
+            return None;
+        }
+
+        let guard = self.refresh.clone().try_lock_owned().ok()?;
+        *last_refresh = Some(Instant::now());
+
+        Some(guard)
+    }
+}
+
+/// Returns whether the token expires within `duration` from now, or `None` if
+/// its expiry is unknown.
+fn expires_within(token: &Token, duration: Duration) -> Option<bool> {
+    let expiry = token.expires_in_timestamp?;
+
+    Some(match SystemTime::now().checked_add(duration) {
+        Some(deadline) => expiry <= deadline,
+        None => true,
+    })
+}
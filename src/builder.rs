@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{Request, Response};
+use hyper::Body;
+use tame_oauth::gcp::TokenProvider;
+use tower::{buffer::Buffer, retry::Retry, util::BoxService, BoxError, Layer, Service, ServiceExt};
+use tower_http::map_response_body::MapResponseBodyLayer;
+use url::Url;
+
+use crate::{
+    auth::{self, Authenticator, DynTokenProvider, READ_WRITE_SCOPE},
+    body::BodyStreamExt,
+    rate_limit::{Metrics, Throttle},
+    retry::{self, RetryConfig, RetryPolicy},
+    DnsClient, Result,
+};
+
+const DEFAULT_BASE_URL: &str = "https://dns.googleapis.com/";
+const DEFAULT_API_VERSION: &str = "v1";
+const BUFFER_CAPACITY: usize = 1024;
+
+/// A builder for a [`DnsClient`], created with [`DnsClient::builder`].
+pub struct DnsClientBuilder {
+    project_id: String,
+    base_url: Url,
+    api_version: String,
+    authenticate: bool,
+    token_provider: Option<Arc<dyn DynTokenProvider>>,
+    scopes: Vec<String>,
+    retry: Option<RetryConfig>,
+    buffer_capacity: usize,
+    max_concurrent_requests: Option<usize>,
+    requests_per_second: Option<f64>,
+}
+
+impl DnsClientBuilder {
+    pub(crate) fn new(project_id: &str) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            base_url: Url::parse(DEFAULT_BASE_URL).unwrap(),
+            api_version: DEFAULT_API_VERSION.to_string(),
+            authenticate: true,
+            token_provider: None,
+            scopes: vec![READ_WRITE_SCOPE.to_string()],
+            retry: None,
+            buffer_capacity: BUFFER_CAPACITY,
+            max_concurrent_requests: None,
+            requests_per_second: None,
+        }
+    }
+
+    /// Sets the root URL of the API, eg. a Private Service Connect endpoint or
+    /// a local emulator. Defaults to `https://dns.googleapis.com/`.
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Sets the API version requests are made against, eg. `v1beta2`.
+    /// Defaults to `v1`.
+    pub fn api_version(mut self, api_version: &str) -> Self {
+        self.api_version = api_version.to_string();
+        self
+    }
+
+    /// Sends requests without an `Authorization` header, for use with
+    /// emulators that don't check credentials.
+    pub fn disable_authentication(mut self) -> Self {
+        self.authenticate = false;
+        self
+    }
+
+    /// Sets the provider used to fetch OAuth tokens, eg. a
+    /// [`ServiceAccountProvider`](tame_oauth::gcp::ServiceAccountProvider) or
+    /// a [`MetadataServerProvider`](tame_oauth::gcp::MetadataServerProvider).
+    ///
+    /// Defaults to the
+    /// [default provider](tame_oauth::gcp::TokenProviderWrapper::get_default_provider),
+    /// resolved on the first request.
+    pub fn token_provider<P>(mut self, provider: P) -> Self
+    where
+        P: TokenProvider + Send + Sync + 'static,
+    {
+        self.token_provider = Some(auth::dyn_provider(provider));
+        self
+    }
+
+    /// Sets the OAuth scopes requested for tokens, defaults to
+    /// [`READ_WRITE_SCOPE`](crate::auth::READ_WRITE_SCOPE).
+    pub fn scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Retries requests that fail with transient errors, see [`RetryConfig`].
+    /// Requests are not retried by default.
+    pub fn retry(mut self, config: RetryConfig) -> Self {
+        self.retry = Some(config);
+        self
+    }
+
+    /// Sets how many requests may be queued for the underlying service before
+    /// callers have to wait. Defaults to 1024.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn buffer_capacity(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the buffer capacity must be greater than zero"
+        );
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    /// Limits how many requests may be in flight at once, further requests
+    /// wait until one completes. Unlimited by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        assert!(max > 0, "the concurrency limit must be greater than zero");
+        self.max_concurrent_requests = Some(max);
+        self
+    }
+
+    /// Limits how many requests are sent per second, using a token bucket that
+    /// allows bursts of up to one second's worth of requests. Unlimited by
+    /// default.
+    ///
+    /// Retried requests count against the limit too. How long requests waited
+    /// is reported by [`DnsClient::rate_limit_metrics`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_second` isn't a positive, finite number.
+    pub fn requests_per_second(mut self, requests_per_second: f64) -> Self {
+        assert!(
+            requests_per_second.is_finite() && requests_per_second > 0.0,
+            "the rate limit must be a positive number"
+        );
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    /// Builds the client, sending all requests through `service`.
+    ///
+    /// Fails if the project or API version can't be joined onto the base URL.
+    pub fn build<S, B>(self, service: S) -> Result<DnsClient>
+    where
+        S: Service<Request<Body>, Response = Response<B>> + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+        B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let service = MapResponseBodyLayer::new(|b: B| Body::wrap_stream(b.into_stream()))
+            .layer(service)
+            .map_err(|e| e.into())
+            .map_request(|request: Request<Bytes>| request.map(Body::from));
+        let mut service = BoxService::new(service);
+
+        let rate_limit =
+            if self.max_concurrent_requests.is_some() || self.requests_per_second.is_some() {
+                let metrics = Arc::new(Metrics::default());
+                // Applied below retries, so that each attempt counts.
+                let inner = Buffer::new(service, self.buffer_capacity);
+                service = BoxService::new(Throttle::new(
+                    inner,
+                    self.max_concurrent_requests,
+                    self.requests_per_second,
+                    metrics.clone(),
+                ));
+                Some(metrics)
+            } else {
+                None
+            };
+
+        if let Some(config) = self.retry {
+            // Retries need a cloneable service to send each attempt through.
+            let inner = Buffer::new(
+                BoxService::new(service.and_then(retry::mark_rate_limited)),
+                self.buffer_capacity,
+            );
+            service = BoxService::new(Retry::new(RetryPolicy::new(config), inner));
+        }
+
+        let mut root = self.base_url;
+        // Make sure any path on the base URL is kept when joining.
+        if !root.path().ends_with('/') {
+            root.set_path(&format!("{}/", root.path()));
+        }
+
+        let base_url = root.join(&format!(
+            "dns/{}/projects/{}/",
+            self.api_version, self.project_id
+        ))?;
+
+        let auth = if self.authenticate {
+            Some(Arc::new(Authenticator::new(
+                self.token_provider,
+                self.scopes,
+            )))
+        } else {
+            None
+        };
+
+        Ok(DnsClient {
+            inner: Buffer::new(service, self.buffer_capacity),
+            base_url,
+            auth,
+            rate_limit,
+        })
+    }
+}
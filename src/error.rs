@@ -8,6 +8,8 @@ pub enum DnsError {
     Dns { source: CloudDnsError },
     #[error(transparent)]
     Auth(#[from] tame_oauth::Error),
+    #[error("no token provider was configured and no default credentials were found")]
+    MissingCredentials,
     #[error(transparent)]
     Json(#[from] serde_path_to_error::Error<serde_json::Error>),
     #[error(transparent)]
@@ -20,6 +22,18 @@ pub enum DnsError {
     Hyper(#[from] hyper::Error),
     #[error(transparent)]
     FromUtf8(#[from] std::string::FromUtf8Error),
+    #[error("timed out after {elapsed:?} waiting for {what}")]
+    Timeout {
+        what: String,
+        elapsed: std::time::Duration,
+    },
+    #[error(transparent)]
+    Sync(#[from] crate::sync::SyncError),
+    /// A read-modify-write of record sets or an IAM policy lost against a
+    /// concurrent change, eg. the record sets being replaced no longer
+    /// matched the change's deletions.
+    #[error("the resource was changed concurrently: {source}")]
+    Conflict { source: CloudDnsError },
     #[error("ServiceError: {0}")]
     Service(tower::BoxError),
     #[error(transparent)]
@@ -28,17 +42,217 @@ pub enum DnsError {
     },
 }
 
+impl DnsError {
+    /// Classifies the error, see [`ErrorKind`].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Dns { source } => source.kind(),
+            Self::Auth(_) | Self::MissingCredentials => ErrorKind::Unauthenticated,
+            Self::Hyper(_) | Self::Service(_) => ErrorKind::Transport,
+            Self::Timeout { .. } => ErrorKind::Timeout,
+            Self::Sync(crate::sync::SyncError::NotOwned { .. }) => ErrorKind::Conflict,
+            Self::Conflict { .. } => ErrorKind::Conflict,
+            Self::Sync(_) => ErrorKind::InvalidArgument,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// The resource (zone, record set, change...) doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        self.kind() == ErrorKind::NotFound
+    }
+
+    /// The resource already exists, or a change conflicts with the current
+    /// state of the zone.
+    pub fn is_conflict(&self) -> bool {
+        self.kind() == ErrorKind::Conflict
+    }
+
+    /// A precondition of the request, eg. the current contents of a record
+    /// set being deleted, no longer holds.
+    pub fn is_precondition_failed(&self) -> bool {
+        self.kind() == ErrorKind::PreconditionFailed
+    }
+
+    /// The request was rejected due to rate limiting.
+    pub fn is_rate_limited(&self) -> bool {
+        self.kind() == ErrorKind::RateLimited
+    }
+
+    /// Whether sending the same request again might succeed.
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// Waiting for a change or operation to complete took too long.
+    pub fn is_timeout(&self) -> bool {
+        self.kind() == ErrorKind::Timeout
+    }
+
+    /// The request lacked valid credentials, or the credentials don't have
+    /// permission to perform it.
+    pub fn is_auth(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Unauthenticated | ErrorKind::PermissionDenied
+        )
+    }
+}
+
+/// A coarse classification of a [`DnsError`], derived from the HTTP status and
+/// the `reason` of the errors returned by Cloud DNS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    NotFound,
+    Conflict,
+    PreconditionFailed,
+    RateLimited,
+    QuotaExceeded,
+    Unauthenticated,
+    PermissionDenied,
+    InvalidArgument,
+    /// The API failed to handle the request, eg. `500 internalError` or
+    /// `503 backendError`.
+    Unavailable,
+    /// The request never got a response from the API.
+    Transport,
+    /// Waiting for a change or operation to complete took too long.
+    Timeout,
+    Other,
+}
+
+impl ErrorKind {
+    /// Whether sending the same request again might succeed.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited | Self::Unavailable | Self::Transport
+        )
+    }
+
+    fn from_reason(reason: &str) -> Option<Self> {
+        Some(match reason {
+            "notFound" => Self::NotFound,
+            "alreadyExists" | "duplicate" | "conflict" => Self::Conflict,
+            "conditionNotMet" | "preconditionFailed" => Self::PreconditionFailed,
+            "rateLimitExceeded" | "userRateLimitExceeded" => Self::RateLimited,
+            "quotaExceeded" => Self::QuotaExceeded,
+            "authError" | "required" | "unauthorized" => Self::Unauthenticated,
+            "forbidden" | "insufficientPermissions" | "accessNotConfigured" => {
+                Self::PermissionDenied
+            }
+            "invalid" | "badRequest" | "invalidParameter" | "invalidArgument" => {
+                Self::InvalidArgument
+            }
+            "backendError" | "internalError" => Self::Unavailable,
+            _ => return None,
+        })
+    }
+
+    fn from_status(code: u16) -> Self {
+        match code {
+            400 => Self::InvalidArgument,
+            401 => Self::Unauthenticated,
+            403 => Self::PermissionDenied,
+            404 => Self::NotFound,
+            409 => Self::Conflict,
+            412 => Self::PreconditionFailed,
+            429 => Self::RateLimited,
+            500..=599 => Self::Unavailable,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<CloudDnsError> for DnsError {
+    fn from(source: CloudDnsError) -> Self {
+        Self::Dns { source }
+    }
+}
+
+/// The envelope Google APIs wrap their errors in, `{"error": {...}}`.
+#[derive(serde::Deserialize, Debug)]
+pub(crate) struct ErrorEnvelope {
+    pub error: CloudDnsError,
+}
+
 /// An error returned from Cloud DNS's API.
 #[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 #[non_exhaustive]
 pub struct CloudDnsError {
-    pub errors: Option<Vec<serde_json::Value>>,
+    /// The HTTP status code of the response.
+    pub code: u16,
+    /// A human readable description of the error, this is not stable and
+    /// should not be matched against.
     pub message: String,
+    /// The canonical status of the error, eg. `NOT_FOUND` or `PERMISSION_DENIED`.
+    pub status: Option<String>,
+    /// The individual errors that caused the request to fail.
+    #[serde(default)]
+    pub errors: Vec<ErrorDetail>,
+}
+
+/// A single entry of the `errors` list of a [`CloudDnsError`].
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ErrorDetail {
+    /// The machine readable reason for the error, eg. `notFound`, `forbidden`
+    /// or `rateLimitExceeded`.
+    pub reason: Option<String>,
+    /// The scope of the error, usually `global`.
+    pub domain: Option<String>,
+    pub message: Option<String>,
+    /// The part of the request that caused the error, if any.
+    pub location: Option<String>,
+    pub location_type: Option<String>,
+}
+
+impl CloudDnsError {
+    /// Classifies the error, preferring the `reason` of the individual errors
+    /// over the HTTP status since several reasons share a status, eg.
+    /// `403 rateLimitExceeded` and `403 forbidden`.
+    pub fn kind(&self) -> ErrorKind {
+        self.reasons()
+            .find_map(ErrorKind::from_reason)
+            .unwrap_or_else(|| ErrorKind::from_status(self.code))
+    }
+
+    /// The `reason` of each of the individual errors.
+    pub fn reasons(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().filter_map(|e| e.reason.as_deref())
+    }
+
+    /// Builds an error for a response whose body could not be parsed as a
+    /// Google error envelope.
+    pub(crate) fn from_status(status: http::StatusCode, body: String) -> Self {
+        let message = if body.is_empty() {
+            status
+                .canonical_reason()
+                .unwrap_or("unknown error")
+                .to_string()
+        } else {
+            body
+        };
+
+        Self {
+            code: status.as_u16(),
+            message,
+            status: None,
+            errors: Vec::new(),
+        }
+    }
 }
 
 impl fmt::Display for CloudDnsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Error: {}", self.message)?;
+        write!(f, "Error {}: {}", self.code, self.message)?;
+
+        if let Some(status) = &self.status {
+            write!(f, " ({})", status)?;
+        }
 
         Ok(())
     }
@@ -1,5 +1,7 @@
 use hyper::Body;
 
+use super::error::{CloudDnsError, ErrorEnvelope};
+
 /// A trait for mapping from a `reqwest::Response` to an another type.
 #[async_trait::async_trait]
 pub trait FromResponse: Sized {
@@ -9,10 +11,31 @@ pub trait FromResponse: Sized {
 #[async_trait::async_trait]
 impl<T: serde::de::DeserializeOwned> FromResponse for T {
     async fn from_response(response: http::Response<Body>) -> super::Result<Self> {
+        let status = response.status();
         let body_bytes = hyper::body::to_bytes(response.into_body()).await?;
         let text = String::from_utf8(body_bytes.to_vec())?;
 
-        let de = &mut serde_json::Deserializer::from_str(&text);
+        if !status.is_success() {
+            return Err(map_error(status, text).into());
+        }
+
+        // Successful deletes come back as `204 No Content`.
+        let text = if text.is_empty() {
+            "null"
+        } else {
+            text.as_str()
+        };
+
+        let de = &mut serde_json::Deserializer::from_str(text);
         serde_path_to_error::deserialize(de).map_err(super::error::DnsError::Json)
     }
 }
+
+/// Maps the body of an unsuccessful response to a [`CloudDnsError`], falling
+/// back to the raw body if it isn't a Google error envelope.
+fn map_error(status: http::StatusCode, text: String) -> CloudDnsError {
+    match serde_json::from_str::<ErrorEnvelope>(&text) {
+        Ok(envelope) => envelope.error,
+        Err(_) => CloudDnsError::from_status(status, text),
+    }
+}
@@ -1,32 +1,45 @@
+use bytes::Bytes;
 use from_response::FromResponse;
 use http::{request, Request, Response};
 use hyper::Body;
 use serde::Serialize;
-use tame_oauth::{
-    gcp::{TokenOrRequest, TokenProvider, TokenProviderWrapper},
-    Token,
-};
-use tower::{buffer::Buffer, util::BoxService, BoxError, Layer, Service, ServiceExt};
-use tower_http::map_response_body::MapResponseBodyLayer;
-use url::Url;
+use std::sync::Arc;
+use tame_oauth::{gcp::TokenOrRequest, Token};
+use tower::{buffer::Buffer, util::BoxService, BoxError, Service, ServiceExt};
 
 pub mod api;
+pub mod auth;
 mod body;
-// Add `into_stream()` to `http::Body`
-use body::BodyStreamExt;
-mod error;
+mod builder;
+pub use builder::DnsClientBuilder;
+pub mod error;
 mod from_response;
+pub mod pagination;
+pub mod poll;
+pub mod rate_limit;
+pub mod rdata;
+pub mod retry;
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod zone_file;
 
 pub type Result<T, E = error::DnsError> = std::result::Result<T, E>;
 
 /// The Cloud DNS API client.
 #[derive(Clone)]
 pub struct DnsClient {
-    inner: Buffer<BoxService<Request<Body>, Response<Body>, BoxError>, Request<Body>>,
+    inner: Buffer<BoxService<Request<Bytes>, Response<Body>, BoxError>, Request<Bytes>>,
     pub base_url: url::Url,
+    /// `None` if authentication is disabled.
+    auth: Option<Arc<auth::Authenticator>>,
+    /// `None` if no rate limits are configured.
+    rate_limit: Option<Arc<rate_limit::Metrics>>,
 }
 
 impl DnsClient {
+    /// Creates a client using the default token provider and scopes, see
+    /// [`DnsClient::builder`] to configure them.
     pub fn new<S, B>(service: S, project_id: &str) -> Self
     where
         S: Service<Request<Body>, Response = Response<B>> + Send + 'static,
@@ -35,48 +48,60 @@ impl DnsClient {
         B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
         B::Error: Into<BoxError>,
     {
-        let service = MapResponseBodyLayer::new(|b: B| Body::wrap_stream(b.into_stream()))
-            .layer(service)
-            .map_err(|e| e.into());
+        Self::builder(project_id)
+            .build(service)
+            .expect("the project id can be joined onto the default base URL")
+    }
 
-        let mut base_url = Url::parse("https://dns.googleapis.com").unwrap();
-        base_url.set_path(&format!("dns/v1/projects/{project_id}/"));
+    pub fn builder(project_id: &str) -> DnsClientBuilder {
+        DnsClientBuilder::new(project_id)
+    }
 
-        Self {
-            inner: Buffer::new(BoxService::new(service), 1024),
-            base_url,
-        }
+    /// Returns how long requests have waited for the client's rate limits so
+    /// far, or `None` if no limits are configured.
+    pub fn rate_limit_metrics(&self) -> Option<rate_limit::RateLimitMetrics> {
+        self.rate_limit.as_ref().map(|metrics| metrics.snapshot())
     }
 
-    pub fn changes(&self) -> api::changes::ChangesHandler {
+    pub fn changes(&self) -> api::changes::ChangesHandler<'_> {
         api::changes::ChangesHandler::new(self)
     }
 
-    pub fn dns_keys(&self) -> api::dns_keys::DnsKeysHandler {
+    pub fn dns_keys(&self) -> api::dns_keys::DnsKeysHandler<'_> {
         api::dns_keys::DnsKeysHandler::new(self)
     }
 
     pub fn managed_zone_operations(
         &self,
-    ) -> api::managed_zone_operations::ManagedZoneOperationsHandler {
+    ) -> api::managed_zone_operations::ManagedZoneOperationsHandler<'_> {
         api::managed_zone_operations::ManagedZoneOperationsHandler::new(self)
     }
 
-    pub fn managed_zones(&self) -> api::managed_zones::ManagedZonesHandler {
+    pub fn managed_zones(&self) -> api::managed_zones::ManagedZonesHandler<'_> {
         api::managed_zones::ManagedZonesHandler::new(self)
     }
 
-    pub fn policies(&self) -> api::policies::PoliciesHandler {
+    pub fn policies(&self) -> api::policies::PoliciesHandler<'_> {
         api::policies::PoliciesHandler::new(self)
     }
 
-    pub fn projects(&self) -> api::projects::ProjectsHandler {
+    pub fn projects(&self) -> api::projects::ProjectsHandler<'_> {
         api::projects::ProjectsHandler::new(self)
     }
 
-    pub fn resource_record_sets(&self) -> api::resource_record_sets::ResourceRecordSetsHandler {
+    pub fn resource_record_sets(&self) -> api::resource_record_sets::ResourceRecordSetsHandler<'_> {
         api::resource_record_sets::ResourceRecordSetsHandler::new(self)
     }
+
+    pub fn response_policies(&self) -> api::response_policies::ResponsePoliciesHandler<'_> {
+        api::response_policies::ResponsePoliciesHandler::new(self)
+    }
+
+    pub fn response_policy_rules(
+        &self,
+    ) -> api::response_policy_rules::ResponsePolicyRulesHandler<'_> {
+        api::response_policy_rules::ResponsePolicyRulesHandler::new(self)
+    }
 }
 
 impl DnsClient {
@@ -179,19 +204,26 @@ impl DnsClient {
         url: url::Url,
         method: http::Method,
     ) -> Result<http::request::Builder> {
-        match self.fetch_token().await {
-            Ok(token) => Ok(request::Builder::new()
-                .method(method)
-                .uri(url.to_string())
-                .header(
+        let builder = request::Builder::new().method(method).uri(url.to_string());
+
+        match &self.auth {
+            Some(auth) => {
+                let token = self.fetch_token(auth).await?;
+
+                Ok(builder.header(
                     http::header::AUTHORIZATION,
                     format!("Bearer {}", token.access_token),
-                )),
-            Err(e) => Err(e),
+                ))
+            }
+            None => Ok(builder),
         }
     }
 
     pub async fn execute(&self, request: Request<Body>) -> Result<Response<Body>> {
+        // Buffer the body so that the request can be retried.
+        let (parts, body) = request.into_parts();
+        let request = Request::from_parts(parts, hyper::body::to_bytes(body).await?);
+
         let mut svc = self.inner.clone();
         svc.ready()
             .await
@@ -203,15 +235,65 @@ impl DnsClient {
 }
 
 impl DnsClient {
-    async fn fetch_token(&self) -> Result<Token> {
-        let provider = TokenProviderWrapper::get_default_provider()
-            .expect("unable to read default token provider")
-            .expect("unable to find default token provider");
-
-        match provider
-            .get_token(&["https://www.googleapis.com/auth/ndev.clouddns.readwrite"])
-            .unwrap()
-        {
+    /// Returns a cached token if possible, otherwise fetches a new one.
+    ///
+    /// Concurrent callers share a single fetch, and tokens close to expiry are
+    /// refreshed in the background while the current one is still handed out.
+    async fn fetch_token(&self, auth: &Arc<auth::Authenticator>) -> Result<Token> {
+        if let Some(token) = auth.cache.get() {
+            if auth.cache.should_refresh(&token) {
+                self.spawn_token_refresh(auth);
+            }
+
+            return Ok(token);
+        }
+
+        let _guard = auth.cache.lock().await;
+
+        // Another request may have refreshed the token while we were waiting.
+        if let Some(token) = auth.cache.get() {
+            return Ok(token);
+        }
+
+        let token = self.request_token(auth).await?;
+        auth.cache.set(token.clone());
+
+        Ok(token)
+    }
+
+    fn spawn_token_refresh(&self, auth: &Arc<auth::Authenticator>) {
+        let runtime = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime,
+            // Without a runtime the token is refreshed once it expires instead.
+            Err(_) => return,
+        };
+
+        let guard = match auth.cache.try_start_refresh() {
+            Some(guard) => guard,
+            None => return,
+        };
+
+        let client = self.clone();
+        let auth = auth.clone();
+        runtime.spawn(async move {
+            // Errors are ignored, the next request after the token expires will
+            // retry and surface them.
+            if let Ok(token) = client.request_token(&auth).await {
+                auth.cache.set(token);
+            }
+
+            drop(guard);
+        });
+    }
+
+    /// Fetches a token from the provider, which may involve an OAuth round trip.
+    ///
+    /// Service accounts always fetch a new token, see [`auth::dyn_provider`].
+    async fn request_token(&self, auth: &auth::Authenticator) -> Result<Token> {
+        let provider = auth.provider()?;
+
+        match provider.get_token(&auth.scopes)? {
+            TokenOrRequest::Token(token) => Ok(token),
             TokenOrRequest::Request {
                 request,
                 scope_hash,
@@ -219,34 +301,25 @@ impl DnsClient {
             } => {
                 let (parts, body) = request.into_parts();
 
-                let mut request_builder = request::Builder::new();
+                let mut request_builder = request::Builder::new()
+                    .method(parts.method)
+                    .uri(parts.uri.to_string());
 
                 for (key, value) in parts.headers.iter() {
-                    request_builder
-                        .headers_mut()
-                        .unwrap()
-                        .append(key, value.clone());
+                    request_builder = request_builder.header(key, value);
                 }
 
-                let request = request_builder
-                    .method(parts.method)
-                    .uri(parts.uri.to_string())
-                    .body(Body::from(body))
-                    .unwrap();
-
-                let response = self.execute(request).await.unwrap();
+                let response = self
+                    .execute(request_builder.body(Body::from(body))?)
+                    .await?;
 
                 let mut response_builder = http::Response::builder()
                     .status(response.status())
                     .version(response.version());
 
-                let headers = response_builder.headers_mut().unwrap();
-                headers.extend(
-                    response
-                        .headers()
-                        .into_iter()
-                        .map(|(k, v)| (k.clone(), v.clone())),
-                );
+                for (key, value) in response.headers() {
+                    response_builder = response_builder.header(key, value);
+                }
 
                 provider
                     .parse_token_response(
@@ -256,7 +329,6 @@ impl DnsClient {
                     )
                     .map_err(error::DnsError::Auth)
             }
-            _ => unreachable!(),
         }
     }
 }
@@ -0,0 +1,216 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    stream::{self, BoxStream, Stream},
+    TryStreamExt,
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{error::DnsError, DnsClient, Result};
+
+/// A page of results returned by a `list` call.
+pub trait ListResponse {
+    type Item;
+
+    /// The token to pass as `pageToken` to fetch the next page, `None` if this
+    /// is the last page.
+    fn next_page_token(&self) -> Option<&str>;
+
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// A stream of every item of a `list` call, transparently fetching further
+/// pages as the stream is consumed.
+pub struct ListStream<'client, T> {
+    inner: BoxStream<'client, Result<T>>,
+}
+
+impl<'client, T: Send + 'client> ListStream<'client, T> {
+    /// Creates a stream that fetches the pages of `route` with the query
+    /// parameters of `request`, following `nextPageToken` until there are no
+    /// more pages.
+    pub(crate) fn new<L, R>(client: &'client DnsClient, route: String, request: R) -> Self
+    where
+        L: ListResponse<Item = T> + DeserializeOwned + Send + 'client,
+        R: ListRequest + Clone + Send + 'client,
+    {
+        Self::from_fn(move |page_token| {
+            let mut request = request.clone();
+            // The first page is the one requested by the caller, if any.
+            if page_token.is_some() {
+                request.set_page_token(page_token);
+            }
+            let route = with_query(route.clone(), &request);
+
+            async move { client.get::<L, _>(route).await }
+        })
+    }
+
+    /// Creates a stream that calls `fetch` with the token of the next page,
+    /// starting with `None` for the first page, until there are no more pages.
+    pub(crate) fn from_fn<L, F, Fut>(mut fetch: F) -> Self
+    where
+        L: ListResponse<Item = T> + Send + 'client,
+        F: FnMut(Option<String>) -> Fut + Send + 'client,
+        Fut: Future<Output = Result<L>> + Send + 'client,
+    {
+        enum State {
+            Start,
+            Next(String),
+            Done,
+        }
+
+        let pages = stream::try_unfold(State::Start, move |state| {
+            let page = match state {
+                State::Start => Some(fetch(None)),
+                State::Next(token) => Some(fetch(Some(token))),
+                State::Done => None,
+            };
+
+            async move {
+                let page = match page {
+                    Some(page) => page.await?,
+                    None => return Ok::<_, DnsError>(None),
+                };
+
+                let next = match page.next_page_token() {
+                    Some(token) if !token.is_empty() => State::Next(token.to_string()),
+                    _ => State::Done,
+                };
+
+                Ok(Some((page.into_items(), next)))
+            }
+        });
+
+        Self {
+            inner: Box::pin(
+                pages
+                    .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+                    .try_flatten(),
+            ),
+        }
+    }
+
+    /// Fetches every page and collects all of the items.
+    pub async fn collect_all(self) -> Result<Vec<T>> {
+        self.try_collect().await
+    }
+}
+
+impl<T> Stream for ListStream<'_, T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// The query parameters of a `list` call.
+pub trait ListRequest {
+    /// The query parameters to send, in the order they are appended to the
+    /// URL.
+    fn query_pairs(&self) -> Vec<(&'static str, String)>;
+
+    /// Sets the `pageToken` used to fetch a specific page.
+    fn set_page_token(&mut self, page_token: Option<String>);
+}
+
+/// The paging parameters shared by every `list` call, and the whole request
+/// of those that take no other parameters.
+#[derive(Debug, Clone, Default)]
+pub struct PageRequest {
+    max_results: Option<u32>,
+    page_token: Option<String>,
+}
+
+impl PageRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of results per page, the server may return fewer.
+    pub fn max_results(mut self, max_results: u32) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// The token of the page to fetch, as returned in `nextPageToken`.
+    pub fn page_token(mut self, page_token: impl Into<String>) -> Self {
+        self.page_token = Some(page_token.into());
+        self
+    }
+}
+
+impl ListRequest for PageRequest {
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+
+        if let Some(max_results) = self.max_results {
+            pairs.push(("maxResults", max_results.to_string()));
+        }
+        if let Some(page_token) = &self.page_token {
+            pairs.push(("pageToken", page_token.clone()));
+        }
+
+        pairs
+    }
+
+    fn set_page_token(&mut self, page_token: Option<String>) {
+        self.page_token = page_token;
+    }
+}
+
+/// Defines the [`PageRequest`] builder methods on a `list` request with a
+/// `page: PageRequest` field.
+macro_rules! page_request_methods {
+    () => {
+        /// The maximum number of results per page, the server may return fewer.
+        pub fn max_results(mut self, max_results: u32) -> Self {
+            self.page = self.page.max_results(max_results);
+            self
+        }
+
+        /// The token of the page to fetch, as returned in `nextPageToken`.
+        pub fn page_token(mut self, page_token: impl Into<String>) -> Self {
+            self.page = self.page.page_token(page_token);
+            self
+        }
+    };
+}
+pub(crate) use page_request_methods;
+
+/// The order in which to sort results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ascending => "ascending",
+            Self::Descending => "descending",
+        }
+    }
+}
+
+/// Appends the query parameters of `request` to `route`.
+pub(crate) fn with_query(route: String, request: &impl ListRequest) -> String {
+    let pairs = request.query_pairs();
+
+    if pairs.is_empty() {
+        return route;
+    }
+
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish();
+
+    format!("{}?{}", route, query)
+}
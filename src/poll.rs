@@ -0,0 +1,140 @@
+//! Polling with exponential backoff, used to wait for changes and operations
+//! to complete.
+
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{error::DnsError, Result};
+
+/// How often, and for how long, to poll for completion.
+#[derive(Debug, Clone)]
+pub struct PollOptions {
+    /// The delay between the first and second poll.
+    pub initial_interval: Duration,
+    /// The upper bound of the delay between polls.
+    pub max_interval: Duration,
+    /// The factor the delay grows by after each poll.
+    pub multiplier: f64,
+    /// How long to wait in total before giving up with [`DnsError::Timeout`].
+    pub timeout: Duration,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_millis(500),
+            max_interval: Duration::from_secs(10),
+            multiplier: 2.0,
+            timeout: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl PollOptions {
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn initial_interval(mut self, initial_interval: Duration) -> Self {
+        self.initial_interval = initial_interval;
+        self
+    }
+
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+}
+
+/// Exponentially growing delays, each randomly reduced by up to half so that
+/// concurrent pollers spread out.
+pub(crate) struct Backoff {
+    next: Duration,
+    max: Duration,
+    multiplier: f64,
+}
+
+impl Backoff {
+    pub(crate) fn new(initial: Duration, max: Duration, multiplier: f64) -> Self {
+        Self {
+            next: initial,
+            max,
+            multiplier,
+        }
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.next.min(self.max);
+        self.next = self.next.mul_f64(self.multiplier.max(1.0)).min(self.max);
+
+        Some(jitter(delay))
+    }
+}
+
+/// Returns a random duration between half of `delay` and `delay`.
+pub(crate) fn jitter(delay: Duration) -> Duration {
+    // `RandomState` is randomly seeded, which is plenty for spreading out
+    // retries without pulling in a random number generator.
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    let fraction = (hasher.finish() % 1000) as f64 / 1000.0;
+
+    delay.mul_f64(0.5 + fraction / 2.0)
+}
+
+/// Calls `fetch` until `is_done` returns true for its result, sleeping with
+/// backoff between calls, or fails with [`DnsError::Timeout`] once the
+/// timeout of `options` would be exceeded.
+///
+/// `fetch` is always called at least once, and each call is cut short if it
+/// would run past the timeout.
+pub(crate) async fn poll_until<T, F, Fut, D>(
+    options: &PollOptions,
+    what: &str,
+    mut fetch: F,
+    mut is_done: D,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+    D: FnMut(&T) -> bool,
+{
+    let start = Instant::now();
+    let timeout = || DnsError::Timeout {
+        what: what.to_string(),
+        elapsed: start.elapsed(),
+    };
+    let mut backoff = Backoff::new(
+        options.initial_interval,
+        options.max_interval,
+        options.multiplier,
+    );
+
+    loop {
+        let remaining = options.timeout.saturating_sub(start.elapsed());
+        let value = tokio::time::timeout(remaining, fetch())
+            .await
+            .map_err(|_| timeout())??;
+        if is_done(&value) {
+            return Ok(value);
+        }
+
+        let delay = backoff.next().unwrap_or(options.max_interval);
+        if start.elapsed() + delay > options.timeout {
+            return Err(timeout());
+        }
+
+        tokio::time::sleep(delay).await;
+    }
+}
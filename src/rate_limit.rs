@@ -0,0 +1,201 @@
+//! Client side limits on the number and rate of requests, to stay within the
+//! Cloud DNS [quotas](https://cloud.google.com/dns/quotas).
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Semaphore;
+use tower::{BoxError, Service};
+
+/// How long requests waited for the rate limits configured with
+/// [`DnsClientBuilder::max_concurrent_requests`](crate::DnsClientBuilder::max_concurrent_requests)
+/// and [`DnsClientBuilder::requests_per_second`](crate::DnsClientBuilder::requests_per_second).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitMetrics {
+    /// The number of requests that passed the limits.
+    pub requests: u64,
+    /// The number of requests that had to wait at all.
+    pub delayed_requests: u64,
+    /// The total time all requests spent waiting.
+    pub total_wait: Duration,
+    /// The longest time a single request spent waiting.
+    pub max_wait: Duration,
+}
+
+impl RateLimitMetrics {
+    /// The average time a request spent waiting.
+    pub fn mean_wait(&self) -> Duration {
+        match self.requests {
+            0 => Duration::ZERO,
+            requests => {
+                Duration::from_nanos((self.total_wait.as_nanos() / u128::from(requests)) as u64)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Metrics {
+    requests: AtomicU64,
+    delayed_requests: AtomicU64,
+    total_wait_nanos: AtomicU64,
+    max_wait_nanos: AtomicU64,
+}
+
+impl Metrics {
+    fn record(&self, wait: Duration) {
+        let nanos = wait.as_nanos() as u64;
+
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if nanos > 0 {
+            self.delayed_requests.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_wait_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> RateLimitMetrics {
+        RateLimitMetrics {
+            requests: self.requests.load(Ordering::Relaxed),
+            delayed_requests: self.delayed_requests.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(self.total_wait_nanos.load(Ordering::Relaxed)),
+            max_wait: Duration::from_nanos(self.max_wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A token bucket allowing `rate` requests per second, with bursts of up to
+/// `capacity` requests.
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        // Allow a second's worth of requests to burst.
+        let capacity = rate.max(1.0);
+
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token, or returns how long to wait until one is available.
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+        state.refilled_at = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - state.tokens) / self.rate))
+        }
+    }
+
+    async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// A service that delays requests until they fit within the configured
+/// concurrency and rate limits.
+#[derive(Clone)]
+pub(crate) struct Throttle<S> {
+    inner: S,
+    concurrency: Option<Arc<Semaphore>>,
+    bucket: Option<Arc<TokenBucket>>,
+    metrics: Arc<Metrics>,
+}
+
+impl<S> Throttle<S> {
+    pub(crate) fn new(
+        inner: S,
+        max_concurrent_requests: Option<usize>,
+        requests_per_second: Option<f64>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self {
+            inner,
+            concurrency: max_concurrent_requests.map(|max| Arc::new(Semaphore::new(max))),
+            bucket: requests_per_second.map(|rate| Arc::new(TokenBucket::new(rate))),
+            metrics,
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for Throttle<S>
+where
+    S: Service<Request> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+    Request: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness of the inner service is checked once the request has
+        // passed the limits.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // `poll_ready` doesn't poll the inner service, so each request drives
+        // its own clone to readiness once it has passed the limits.
+        let mut inner = self.inner.clone();
+        let concurrency = self.concurrency.clone();
+        let bucket = self.bucket.clone();
+        let metrics = self.metrics.clone();
+
+        Box::pin(async move {
+            let start = Instant::now();
+
+            let _permit = match concurrency {
+                Some(semaphore) => Some(semaphore.acquire_owned().await?),
+                None => None,
+            };
+
+            if let Some(bucket) = bucket {
+                bucket.acquire().await;
+            }
+
+            metrics.record(start.elapsed());
+
+            futures::future::poll_fn(|cx| inner.poll_ready(cx))
+                .await
+                .map_err(Into::into)?;
+            inner.call(request).await.map_err(Into::into)
+        })
+    }
+}
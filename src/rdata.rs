@@ -0,0 +1,911 @@
+//! Typed record types and record data, parsed from and rendered to the
+//! presentation format used in [`ResourceRecordSet::rrdatas`](crate::api::resource_record_sets::ResourceRecordSet::rrdatas).
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The record types supported by Cloud DNS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(from = "String", into = "String")]
+#[non_exhaustive]
+pub enum RecordType {
+    A,
+    AAAA,
+    CAA,
+    CNAME,
+    DNSKEY,
+    DS,
+    HTTPS,
+    IPSECKEY,
+    MX,
+    NAPTR,
+    NS,
+    PTR,
+    SOA,
+    SPF,
+    SRV,
+    SSHFP,
+    SVCB,
+    TLSA,
+    TXT,
+    /// A type returned by the API that this crate doesn't know about, so that
+    /// listing record sets doesn't fail when new types are added.
+    Unknown(String),
+}
+
+impl RecordType {
+    pub const ALL: [RecordType; 19] = [
+        Self::A,
+        Self::AAAA,
+        Self::CAA,
+        Self::CNAME,
+        Self::DNSKEY,
+        Self::DS,
+        Self::HTTPS,
+        Self::IPSECKEY,
+        Self::MX,
+        Self::NAPTR,
+        Self::NS,
+        Self::PTR,
+        Self::SOA,
+        Self::SPF,
+        Self::SRV,
+        Self::SSHFP,
+        Self::SVCB,
+        Self::TLSA,
+        Self::TXT,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::A => "A",
+            Self::AAAA => "AAAA",
+            Self::CAA => "CAA",
+            Self::CNAME => "CNAME",
+            Self::DNSKEY => "DNSKEY",
+            Self::DS => "DS",
+            Self::HTTPS => "HTTPS",
+            Self::IPSECKEY => "IPSECKEY",
+            Self::MX => "MX",
+            Self::NAPTR => "NAPTR",
+            Self::NS => "NS",
+            Self::PTR => "PTR",
+            Self::SOA => "SOA",
+            Self::SPF => "SPF",
+            Self::SRV => "SRV",
+            Self::SSHFP => "SSHFP",
+            Self::SVCB => "SVCB",
+            Self::TLSA => "TLSA",
+            Self::TXT => "TXT",
+            Self::Unknown(r#type) => r#type,
+        }
+    }
+}
+
+impl From<String> for RecordType {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or(Self::Unknown(s))
+    }
+}
+
+impl From<RecordType> for String {
+    fn from(r#type: RecordType) -> Self {
+        match r#type {
+            RecordType::Unknown(r#type) => r#type,
+            r#type => r#type.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| RDataError::new(format!("unknown record type '{}'", s)))
+    }
+}
+
+/// An error parsing record data.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid record data: {message}")]
+pub struct RDataError {
+    message: String,
+}
+
+impl RDataError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// The data of a single record, ie. one entry of `rrdatas`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RData {
+    A(Ipv4Addr),
+    AAAA(Ipv6Addr),
+    CAA(Caa),
+    CNAME(String),
+    DNSKEY(Dnskey),
+    DS(Ds),
+    HTTPS(Svcb),
+    IPSECKEY(Ipseckey),
+    MX(Mx),
+    NAPTR(Naptr),
+    NS(String),
+    PTR(String),
+    SOA(Soa),
+    SPF(Txt),
+    SRV(Srv),
+    SSHFP(Sshfp),
+    SVCB(Svcb),
+    TLSA(Tlsa),
+    TXT(Txt),
+}
+
+impl RData {
+    /// Parses the presentation format of a record of type `record_type`.
+    pub fn parse(record_type: RecordType, s: &str) -> Result<Self, RDataError> {
+        Ok(match record_type {
+            RecordType::A => Self::A(parse_value(s.trim(), "IPv4 address")?),
+            RecordType::AAAA => Self::AAAA(parse_value(s.trim(), "IPv6 address")?),
+            RecordType::CAA => Self::CAA(s.parse()?),
+            RecordType::CNAME => Self::CNAME(parse_name(s)?),
+            RecordType::DNSKEY => Self::DNSKEY(s.parse()?),
+            RecordType::DS => Self::DS(s.parse()?),
+            RecordType::HTTPS => Self::HTTPS(s.parse()?),
+            RecordType::IPSECKEY => Self::IPSECKEY(s.parse()?),
+            RecordType::MX => Self::MX(s.parse()?),
+            RecordType::NAPTR => Self::NAPTR(s.parse()?),
+            RecordType::NS => Self::NS(parse_name(s)?),
+            RecordType::PTR => Self::PTR(parse_name(s)?),
+            RecordType::SOA => Self::SOA(s.parse()?),
+            RecordType::SPF => Self::SPF(s.parse()?),
+            RecordType::SRV => Self::SRV(s.parse()?),
+            RecordType::SSHFP => Self::SSHFP(s.parse()?),
+            RecordType::SVCB => Self::SVCB(s.parse()?),
+            RecordType::TLSA => Self::TLSA(s.parse()?),
+            RecordType::TXT => Self::TXT(s.parse()?),
+            RecordType::Unknown(r#type) => {
+                return Err(RDataError::new(format!(
+                    "unsupported record type '{}'",
+                    r#type
+                )))
+            }
+        })
+    }
+
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            Self::A(_) => RecordType::A,
+            Self::AAAA(_) => RecordType::AAAA,
+            Self::CAA(_) => RecordType::CAA,
+            Self::CNAME(_) => RecordType::CNAME,
+            Self::DNSKEY(_) => RecordType::DNSKEY,
+            Self::DS(_) => RecordType::DS,
+            Self::HTTPS(_) => RecordType::HTTPS,
+            Self::IPSECKEY(_) => RecordType::IPSECKEY,
+            Self::MX(_) => RecordType::MX,
+            Self::NAPTR(_) => RecordType::NAPTR,
+            Self::NS(_) => RecordType::NS,
+            Self::PTR(_) => RecordType::PTR,
+            Self::SOA(_) => RecordType::SOA,
+            Self::SPF(_) => RecordType::SPF,
+            Self::SRV(_) => RecordType::SRV,
+            Self::SSHFP(_) => RecordType::SSHFP,
+            Self::SVCB(_) => RecordType::SVCB,
+            Self::TLSA(_) => RecordType::TLSA,
+            Self::TXT(_) => RecordType::TXT,
+        }
+    }
+}
+
+impl fmt::Display for RData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A(addr) => addr.fmt(f),
+            Self::AAAA(addr) => addr.fmt(f),
+            Self::CAA(caa) => caa.fmt(f),
+            Self::CNAME(name) | Self::NS(name) | Self::PTR(name) => f.write_str(name),
+            Self::DNSKEY(dnskey) => dnskey.fmt(f),
+            Self::DS(ds) => ds.fmt(f),
+            Self::HTTPS(svcb) | Self::SVCB(svcb) => svcb.fmt(f),
+            Self::IPSECKEY(ipseckey) => ipseckey.fmt(f),
+            Self::MX(mx) => mx.fmt(f),
+            Self::NAPTR(naptr) => naptr.fmt(f),
+            Self::SOA(soa) => soa.fmt(f),
+            Self::SPF(txt) | Self::TXT(txt) => txt.fmt(f),
+            Self::SRV(srv) => srv.fmt(f),
+            Self::SSHFP(sshfp) => sshfp.fmt(f),
+            Self::TLSA(tlsa) => tlsa.fmt(f),
+        }
+    }
+}
+
+/// Certification Authority Authorization, eg. `0 issue "letsencrypt.org"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caa {
+    pub flags: u8,
+    pub tag: String,
+    pub value: String,
+}
+
+impl FromStr for Caa {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let caa = Self {
+            flags: tokens.next_value("flags")?,
+            tag: tokens.next_plain("tag")?,
+            value: tokens.next_string("value")?,
+        };
+        tokens.finish()?;
+
+        Ok(caa)
+    }
+}
+
+impl fmt::Display for Caa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ", self.flags, self.tag)?;
+        write_quoted(f, &self.value)
+    }
+}
+
+/// A DNSSEC public key, eg. `257 3 8 AwEAAb...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dnskey {
+    pub flags: u16,
+    pub protocol: u8,
+    pub algorithm: u8,
+    /// Base64 encoded.
+    pub public_key: String,
+}
+
+impl FromStr for Dnskey {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+
+        Ok(Self {
+            flags: tokens.next_value("flags")?,
+            protocol: tokens.next_value("protocol")?,
+            algorithm: tokens.next_value("algorithm")?,
+            public_key: tokens.rest("public key")?,
+        })
+    }
+}
+
+impl fmt::Display for Dnskey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.flags, self.protocol, self.algorithm, self.public_key
+        )
+    }
+}
+
+/// A delegation signer, eg. `12345 8 2 49FD46E6C4B45C55D4AC...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ds {
+    pub key_tag: u16,
+    pub algorithm: u8,
+    pub digest_type: u8,
+    /// Hex encoded.
+    pub digest: String,
+}
+
+impl FromStr for Ds {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+
+        Ok(Self {
+            key_tag: tokens.next_value("key tag")?,
+            algorithm: tokens.next_value("algorithm")?,
+            digest_type: tokens.next_value("digest type")?,
+            digest: tokens.rest("digest")?,
+        })
+    }
+}
+
+impl fmt::Display for Ds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key_tag, self.algorithm, self.digest_type, self.digest
+        )
+    }
+}
+
+/// Service binding, used by both `SVCB` and `HTTPS` records, eg.
+/// `1 . alpn="h2,h3"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Svcb {
+    pub priority: u16,
+    pub target: String,
+    /// The service parameters as `key` or `key=value` pairs, in order.
+    pub params: Vec<(String, Option<String>)>,
+}
+
+impl FromStr for Svcb {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let priority = tokens.next_value("priority")?;
+        let target = tokens.next_plain("target")?;
+
+        let mut params = Vec::new();
+        for token in tokens {
+            if token.quoted {
+                return Err(RDataError::new(format!(
+                    "unexpected quoted string \"{}\"",
+                    token.text
+                )));
+            }
+
+            // The tokenizer has already unquoted the value of `key="value"`.
+            match token.text.split_once('=') {
+                Some((key, value)) => params.push((key.to_string(), Some(value.to_string()))),
+                None => params.push((token.text, None)),
+            }
+        }
+
+        Ok(Self {
+            priority,
+            target,
+            params,
+        })
+    }
+}
+
+impl fmt::Display for Svcb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.priority, self.target)?;
+
+        for (key, value) in &self.params {
+            write!(f, " {}", key)?;
+
+            match value {
+                Some(value)
+                    if value.is_empty()
+                        || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') =>
+                {
+                    f.write_str("=")?;
+                    write_quoted(f, value)?;
+                }
+                Some(value) => write!(f, "={}", value)?,
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An IPsec keying material, eg. `10 1 2 192.0.2.38 AQNRU3mG7TVTO2Bk...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipseckey {
+    pub precedence: u8,
+    pub gateway_type: u8,
+    pub algorithm: u8,
+    /// `.` if there is no gateway.
+    pub gateway: String,
+    /// Base64 encoded, empty if there is no key.
+    pub public_key: String,
+}
+
+impl FromStr for Ipseckey {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+
+        Ok(Self {
+            precedence: tokens.next_value("precedence")?,
+            gateway_type: tokens.next_value("gateway type")?,
+            algorithm: tokens.next_value("algorithm")?,
+            gateway: tokens.next_plain("gateway")?,
+            public_key: tokens.rest("public key").unwrap_or_default(),
+        })
+    }
+}
+
+impl fmt::Display for Ipseckey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.precedence, self.gateway_type, self.algorithm, self.gateway
+        )?;
+
+        if !self.public_key.is_empty() {
+            write!(f, " {}", self.public_key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A mail exchange, eg. `10 mail.example.com.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mx {
+    pub preference: u16,
+    pub exchange: String,
+}
+
+impl FromStr for Mx {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let mx = Self {
+            preference: tokens.next_value("preference")?,
+            exchange: tokens.next_plain("exchange")?,
+        };
+        tokens.finish()?;
+
+        Ok(mx)
+    }
+}
+
+impl fmt::Display for Mx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.preference, self.exchange)
+    }
+}
+
+/// A naming authority pointer, eg. `100 10 "S" "SIP+D2U" "" _sip._udp.example.com.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Naptr {
+    pub order: u16,
+    pub preference: u16,
+    pub flags: String,
+    pub service: String,
+    pub regexp: String,
+    pub replacement: String,
+}
+
+impl FromStr for Naptr {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let naptr = Self {
+            order: tokens.next_value("order")?,
+            preference: tokens.next_value("preference")?,
+            flags: tokens.next_string("flags")?,
+            service: tokens.next_string("service")?,
+            regexp: tokens.next_string("regexp")?,
+            replacement: tokens.next_plain("replacement")?,
+        };
+        tokens.finish()?;
+
+        Ok(naptr)
+    }
+}
+
+impl fmt::Display for Naptr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ", self.order, self.preference)?;
+        write_quoted(f, &self.flags)?;
+        f.write_str(" ")?;
+        write_quoted(f, &self.service)?;
+        f.write_str(" ")?;
+        write_quoted(f, &self.regexp)?;
+        write!(f, " {}", self.replacement)
+    }
+}
+
+/// The start of authority of a zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Soa {
+    pub mname: String,
+    pub rname: String,
+    pub serial: u32,
+    pub refresh: u32,
+    pub retry: u32,
+    pub expire: u32,
+    pub minimum: u32,
+}
+
+impl FromStr for Soa {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let soa = Self {
+            mname: tokens.next_plain("mname")?,
+            rname: tokens.next_plain("rname")?,
+            serial: tokens.next_value("serial")?,
+            refresh: tokens.next_value("refresh")?,
+            retry: tokens.next_value("retry")?,
+            expire: tokens.next_value("expire")?,
+            minimum: tokens.next_value("minimum")?,
+        };
+        tokens.finish()?;
+
+        Ok(soa)
+    }
+}
+
+impl fmt::Display for Soa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {}",
+            self.mname,
+            self.rname,
+            self.serial,
+            self.refresh,
+            self.retry,
+            self.expire,
+            self.minimum
+        )
+    }
+}
+
+/// A service location, eg. `10 5 5060 sip.example.com.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Srv {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+impl FromStr for Srv {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+        let srv = Self {
+            priority: tokens.next_value("priority")?,
+            weight: tokens.next_value("weight")?,
+            port: tokens.next_value("port")?,
+            target: tokens.next_plain("target")?,
+        };
+        tokens.finish()?;
+
+        Ok(srv)
+    }
+}
+
+impl fmt::Display for Srv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.priority, self.weight, self.port, self.target
+        )
+    }
+}
+
+/// An SSH public key fingerprint, eg. `4 2 123456789abcdef...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sshfp {
+    pub algorithm: u8,
+    pub fingerprint_type: u8,
+    /// Hex encoded.
+    pub fingerprint: String,
+}
+
+impl FromStr for Sshfp {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+
+        Ok(Self {
+            algorithm: tokens.next_value("algorithm")?,
+            fingerprint_type: tokens.next_value("fingerprint type")?,
+            fingerprint: tokens.rest("fingerprint")?,
+        })
+    }
+}
+
+impl fmt::Display for Sshfp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.algorithm, self.fingerprint_type, self.fingerprint
+        )
+    }
+}
+
+/// A TLS certificate association, eg. `3 1 1 0123456789abcdef...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tlsa {
+    pub usage: u8,
+    pub selector: u8,
+    pub matching_type: u8,
+    /// Hex encoded.
+    pub data: String,
+}
+
+impl FromStr for Tlsa {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s)?;
+
+        Ok(Self {
+            usage: tokens.next_value("usage")?,
+            selector: tokens.next_value("selector")?,
+            matching_type: tokens.next_value("matching type")?,
+            data: tokens.rest("certificate association data")?,
+        })
+    }
+}
+
+impl fmt::Display for Tlsa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.usage, self.selector, self.matching_type, self.data
+        )
+    }
+}
+
+/// The character strings of a `TXT` or `SPF` record, without quotes or
+/// escapes. Each string may be at most 255 bytes long, [`Txt::new`] splits
+/// longer values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Txt(pub Vec<String>);
+
+impl Txt {
+    /// The longest character string a record can hold.
+    pub const MAX_STRING_LEN: usize = 255;
+
+    /// A record holding `value`, split into as many strings of at most
+    /// [`MAX_STRING_LEN`](Self::MAX_STRING_LEN) bytes as needed without
+    /// splitting any character.
+    pub fn new(value: &str) -> Self {
+        let mut strings = Vec::new();
+        let mut rest = value;
+
+        while rest.len() > Self::MAX_STRING_LEN {
+            let mut end = Self::MAX_STRING_LEN;
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let (string, tail) = rest.split_at(end);
+            strings.push(string.to_string());
+            rest = tail;
+        }
+        strings.push(rest.to_string());
+
+        Self(strings)
+    }
+
+    /// The strings concatenated, which is how most consumers interpret them.
+    pub fn joined(&self) -> String {
+        self.0.concat()
+    }
+}
+
+impl FromStr for Txt {
+    type Err = RDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strings: Vec<_> = Tokens::new(s)?.map(|token| token.text).collect();
+
+        if strings.is_empty() {
+            return Err(RDataError::new("expected at least one character string"));
+        }
+
+        Ok(Self(strings))
+    }
+}
+
+impl fmt::Display for Txt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, string) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write_quoted(f, string)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_value<T: FromStr>(s: &str, what: &str) -> Result<T, RDataError> {
+    s.parse()
+        .map_err(|_| RDataError::new(format!("invalid {} '{}'", what, s)))
+}
+
+fn parse_name(s: &str) -> Result<String, RDataError> {
+    let mut tokens = Tokens::new(s)?;
+    let name = tokens.next_plain("domain name")?;
+    tokens.finish()?;
+
+    Ok(name)
+}
+
+/// Writes `s` as a quoted character string, escaping quotes, backslashes and
+/// non printable bytes.
+pub(crate) fn write_quoted(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => write!(f, "\\{}", c)?,
+            c if c.is_ascii_graphic() || c == ' ' => f.write_char(c)?,
+            c => {
+                let mut buf = [0; 4];
+                for byte in c.encode_utf8(&mut buf).bytes() {
+                    write!(f, "\\{:03}", byte)?;
+                }
+            }
+        }
+    }
+
+    f.write_char('"')
+}
+
+pub(crate) struct Token {
+    pub(crate) text: String,
+    pub(crate) quoted: bool,
+}
+
+/// Splits presentation format into whitespace separated tokens, handling
+/// quoted strings and `\X`/`\DDD` escapes.
+pub(crate) struct Tokens {
+    tokens: std::vec::IntoIter<Token>,
+}
+
+impl Tokens {
+    pub(crate) fn new(s: &str) -> Result<Self, RDataError> {
+        let mut tokens = Vec::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            // A quote also starts a quoted string within a token, eg. the
+            // value of `alpn="h2 h3"`.
+            let quoted = c == '"';
+            let mut in_quotes = false;
+            let mut bytes = Vec::new();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => in_quotes = !in_quotes,
+                    c if c.is_whitespace() && !in_quotes => break,
+                    '\\' => {
+                        let escaped = chars
+                            .next()
+                            .ok_or_else(|| RDataError::new("dangling escape"))?;
+
+                        if let Some(first) = escaped.to_digit(10) {
+                            let mut value = first;
+                            for _ in 0..2 {
+                                let digit = chars
+                                    .next()
+                                    .and_then(|c| c.to_digit(10))
+                                    .ok_or_else(|| RDataError::new("invalid \\DDD escape"))?;
+                                value = value * 10 + digit;
+                            }
+
+                            let byte = u8::try_from(value)
+                                .map_err(|_| RDataError::new("invalid \\DDD escape"))?;
+                            bytes.push(byte);
+                        } else {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                        }
+                    }
+                    c => {
+                        let mut buf = [0; 4];
+                        bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    }
+                }
+            }
+
+            if in_quotes {
+                return Err(RDataError::new("unterminated quoted string"));
+            }
+
+            let text = String::from_utf8(bytes)
+                .map_err(|_| RDataError::new("character string is not valid UTF-8"))?;
+            tokens.push(Token { text, quoted });
+        }
+
+        Ok(Self {
+            tokens: tokens.into_iter(),
+        })
+    }
+
+    fn next_token(&mut self, what: &str) -> Result<Token, RDataError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| RDataError::new(format!("missing {}", what)))
+    }
+
+    fn next_value<T: FromStr>(&mut self, what: &str) -> Result<T, RDataError> {
+        let token = self.next_token(what)?;
+        parse_value(&token.text, what)
+    }
+
+    /// The next token, which must not be quoted, eg. a domain name.
+    fn next_plain(&mut self, what: &str) -> Result<String, RDataError> {
+        let token = self.next_token(what)?;
+
+        if token.quoted {
+            return Err(RDataError::new(format!(
+                "expected {}, found quoted string",
+                what
+            )));
+        }
+
+        Ok(token.text)
+    }
+
+    /// The next token, which may or may not be quoted.
+    fn next_string(&mut self, what: &str) -> Result<String, RDataError> {
+        Ok(self.next_token(what)?.text)
+    }
+
+    /// The remaining tokens concatenated, eg. for base64 data that may
+    /// contain whitespace.
+    fn rest(&mut self, what: &str) -> Result<String, RDataError> {
+        let rest: String = self.tokens.by_ref().map(|token| token.text).collect();
+
+        if rest.is_empty() {
+            return Err(RDataError::new(format!("missing {}", what)));
+        }
+
+        Ok(rest)
+    }
+
+    fn finish(&mut self) -> Result<(), RDataError> {
+        match self.tokens.next() {
+            Some(token) => Err(RDataError::new(format!(
+                "unexpected trailing data '{}'",
+                token.text
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Iterator for Tokens {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.tokens.next()
+    }
+}
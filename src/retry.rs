@@ -0,0 +1,215 @@
+//! Retrying of requests that failed with transient errors.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use http::{Method, Request, Response, StatusCode};
+use hyper::Body;
+use tower::BoxError;
+
+use crate::{
+    error::{ErrorEnvelope, ErrorKind},
+    poll::Backoff,
+};
+
+/// Configures retrying of requests that failed with `429 Too Many Requests`,
+/// a `403` with a `rateLimitExceeded` or `userRateLimitExceeded` reason, a
+/// `5xx` status, or a transport error, see
+/// [`DnsClientBuilder::retry`](crate::DnsClientBuilder::retry).
+///
+/// `GET`, `PUT` and `DELETE` requests are always retried. Other requests,
+/// including creating a change, are only retried when rate limited, which the
+/// API reports before acting on a request. A change that failed with a `5xx`
+/// status may still have been applied, so it isn't sent again blindly.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry, which then grows exponentially.
+    pub initial_backoff: Duration,
+    /// The upper bound of the delay between retries.
+    pub max_backoff: Duration,
+    /// Requests are not retried once this much time has passed since the
+    /// first attempt failed.
+    pub max_elapsed: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_elapsed: Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = max_elapsed;
+        self
+    }
+}
+
+/// The [`tower::retry::Policy`] for a single request.
+#[derive(Clone)]
+pub(crate) struct RetryPolicy {
+    config: RetryConfig,
+    /// The number of attempts made so far.
+    attempts: u32,
+    /// When the first attempt failed.
+    first_failure: Option<Instant>,
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(config: RetryConfig) -> Self {
+        Self {
+            config,
+            attempts: 1,
+            first_failure: None,
+        }
+    }
+
+    /// The delay before the next attempt, ignoring `Retry-After`.
+    fn backoff(&self) -> Duration {
+        Backoff::new(self.config.initial_backoff, self.config.max_backoff, 2.0)
+            .nth(self.attempts as usize - 1)
+            .unwrap_or(self.config.max_backoff)
+    }
+}
+
+impl tower::retry::Policy<Request<Bytes>, Response<Body>, BoxError> for RetryPolicy {
+    type Future = Pin<Box<dyn Future<Output = Self> + Send>>;
+
+    fn retry(
+        &self,
+        request: &Request<Bytes>,
+        result: Result<&Response<Body>, &BoxError>,
+    ) -> Option<Self::Future> {
+        if self.attempts >= self.config.max_attempts {
+            return None;
+        }
+
+        let retry_after = match result {
+            Ok(response) if should_retry_response(request, response) => retry_after(response),
+            Ok(_) => return None,
+            Err(_) if is_idempotent(request) => None,
+            Err(_) => return None,
+        };
+
+        let first_failure = self.first_failure.unwrap_or_else(Instant::now);
+        let delay = retry_after.unwrap_or_else(|| self.backoff());
+
+        if first_failure.elapsed() + delay > self.config.max_elapsed {
+            return None;
+        }
+
+        let next = Self {
+            config: self.config.clone(),
+            attempts: self.attempts + 1,
+            first_failure: Some(first_failure),
+        };
+
+        Some(Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            next
+        }))
+    }
+
+    fn clone_request(&self, request: &Request<Bytes>) -> Option<Request<Bytes>> {
+        let mut builder = Request::builder()
+            .method(request.method().clone())
+            .uri(request.uri().clone())
+            .version(request.version());
+
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(
+                request
+                    .headers()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone())),
+            );
+        }
+
+        builder.body(request.body().clone()).ok()
+    }
+}
+
+/// Marks `403` responses whose error reason is a rate limit, see
+/// [`should_retry_response`], buffering their body to read the reason.
+pub(crate) async fn mark_rate_limited(
+    response: Response<Body>,
+) -> Result<Response<Body>, BoxError> {
+    if response.status() != StatusCode::FORBIDDEN {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+
+    if let Ok(envelope) = serde_json::from_slice::<ErrorEnvelope>(&body) {
+        if envelope.error.kind() == ErrorKind::RateLimited {
+            parts.extensions.insert(RateLimited);
+        }
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+/// Set on `403` responses that were rate limited, by [`mark_rate_limited`].
+#[derive(Clone, Copy)]
+struct RateLimited;
+
+fn should_retry_response(request: &Request<Bytes>, response: &Response<Body>) -> bool {
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS => true,
+        StatusCode::FORBIDDEN => response.extensions().get::<RateLimited>().is_some(),
+        StatusCode::INTERNAL_SERVER_ERROR
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => is_idempotent(request),
+        _ => false,
+    }
+}
+
+/// Whether sending the request twice has the same effect as sending it once.
+fn is_idempotent(request: &Request<Bytes>) -> bool {
+    matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+/// Parses a `Retry-After` header given in seconds.
+fn retry_after(response: &Response<Body>) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(Duration::from_secs(seconds))
+}
@@ -0,0 +1,355 @@
+//! Declarative management of the record sets in a zone.
+//!
+//! [`plan`] compares the desired record sets of a zone with its current ones
+//! and computes the [`Change`] that turns one into the other, which [`apply`]
+//! then submits:
+//!
+//! ```no_run
+//! # async fn run(client: cloud_dns::DnsClient) -> cloud_dns::Result<()> {
+//! use cloud_dns::{api::resource_record_sets::ResourceRecordSet, rdata::RecordType, sync};
+//!
+//! let desired = vec![ResourceRecordSet::new(
+//!     "www.example.com.",
+//!     RecordType::A,
+//!     300,
+//!     vec!["192.0.2.1"],
+//! )];
+//!
+//! let plan = sync::plan(&client, "example-zone", desired, &Default::default()).await?;
+//! if !plan.is_empty() {
+//!     sync::apply(&client, &plan).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+
+use thiserror::Error;
+
+use crate::{
+    api::{changes::Change, resource_record_sets::ResourceRecordSet},
+    rdata::{RData, RecordType, Txt},
+    DnsClient, Result,
+};
+
+/// Configures which record sets [`plan`] manages.
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    manage_apex: bool,
+    ownership_marker: Option<String>,
+}
+
+impl SyncOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also manages the `SOA` and apex `NS` record sets, which Cloud DNS
+    /// creates along with the zone. They are left untouched by default.
+    pub fn manage_apex(mut self, manage_apex: bool) -> Self {
+        self.manage_apex = manage_apex;
+        self
+    }
+
+    /// Only manages names that have a `TXT` record with the text `marker`.
+    ///
+    /// Record sets at other names are neither modified nor deleted, and the
+    /// marker is added to every name in the desired state, so that a zone can
+    /// be shared with records managed by other tools. Planning fails with
+    /// [`SyncError::NotOwned`] if a desired name without the marker already
+    /// holds record sets, other than the zone's `SOA` and `NS` records.
+    pub fn ownership_marker(mut self, marker: impl Into<String>) -> Self {
+        self.ownership_marker = Some(marker.into());
+        self
+    }
+}
+
+/// The changes needed to bring a zone to its desired state, see [`plan`].
+#[derive(Debug, Clone)]
+pub struct Plan {
+    /// The name or id of the zone the plan applies to.
+    pub managed_zone: String,
+    /// The record sets to add and delete. A record set that differs from the
+    /// desired one is deleted and added again.
+    pub change: Change,
+}
+
+impl Plan {
+    /// Whether the zone is already in its desired state.
+    pub fn is_empty(&self) -> bool {
+        self.change.additions.is_empty() && self.change.deletions.is_empty()
+    }
+
+    /// Splits the change into batches of at most `max_additions` additions and
+    /// `max_deletions` deletions.
+    ///
+    /// The deletion and addition that replace a record set are kept in the
+    /// same batch, so that each batch can be applied on its own.
+    pub fn batches(&self, max_additions: usize, max_deletions: usize) -> Vec<Change> {
+        let max_additions = max_additions.max(1);
+        let max_deletions = max_deletions.max(1);
+
+        let mut additions: BTreeMap<_, _> = self
+            .change
+            .additions
+            .iter()
+            .map(|rrset| (key(rrset), rrset))
+            .collect();
+
+        // Replacements first, then plain deletions, then plain additions.
+        let mut items = Vec::new();
+        let mut deletions = Vec::new();
+        for deletion in &self.change.deletions {
+            match additions.remove(&key(deletion)) {
+                Some(addition) => items.push((Some(addition), Some(deletion))),
+                None => deletions.push((None, Some(deletion))),
+            }
+        }
+        items.extend(deletions);
+        items.extend(
+            additions
+                .into_values()
+                .map(|addition| (Some(addition), None)),
+        );
+
+        let mut batches = Vec::new();
+        let mut batch = Change::default();
+        for (addition, deletion) in items {
+            if batch.additions.len() + addition.is_some() as usize > max_additions
+                || batch.deletions.len() + deletion.is_some() as usize > max_deletions
+            {
+                batches.push(std::mem::take(&mut batch));
+            }
+
+            batch.additions.extend(addition.cloned());
+            batch.deletions.extend(deletion.cloned());
+        }
+        if !batch.additions.is_empty() || !batch.deletions.is_empty() {
+            batches.push(batch);
+        }
+
+        batches
+    }
+}
+
+/// A desired state that can't be planned.
+#[derive(Error, Debug)]
+pub enum SyncError {
+    #[error("the desired state contains more than one {r#type} record set for {name}")]
+    Duplicate { name: String, r#type: RecordType },
+    #[error("the {r#type} record set for {name} already exists and isn't marked as managed")]
+    NotOwned { name: String, r#type: RecordType },
+}
+
+/// Computes the minimal change that turns the record sets of `managed_zone`
+/// into `desired`.
+///
+/// Record sets are matched by name and type. One that only differs in the
+/// order of its rrdatas is considered unchanged.
+pub async fn plan(
+    client: &DnsClient,
+    managed_zone: &str,
+    desired: Vec<ResourceRecordSet>,
+    options: &SyncOptions,
+) -> Result<Plan> {
+    let apex = client
+        .managed_zones()
+        .get(managed_zone.to_string())
+        .await?
+        .dns_name;
+
+    let current = client
+        .resource_record_sets()
+        .list_all(managed_zone, Default::default())
+        .collect_all()
+        .await?;
+
+    let change = diff(&apex, current, desired, options)?;
+
+    Ok(Plan {
+        managed_zone: managed_zone.to_string(),
+        change,
+    })
+}
+
+/// Submits the change of `plan`, split into batches within the project's
+/// quota for additions and deletions per change.
+///
+/// Returns the submitted changes, which may still be pending.
+pub async fn apply(client: &DnsClient, plan: &Plan) -> Result<Vec<Change>> {
+    if plan.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let quota = client.projects().get().await?.quota;
+    let batches = plan.batches(
+        quota.rrset_additions_per_change as usize,
+        quota.rrset_deletions_per_change as usize,
+    );
+
+    let mut changes = Vec::with_capacity(batches.len());
+    for batch in batches {
+        changes.push(client.changes().create(&plan.managed_zone, batch).await?);
+    }
+
+    Ok(changes)
+}
+
+type Key = (String, RecordType);
+
+fn key(rrset: &ResourceRecordSet) -> Key {
+    (rrset.name.to_ascii_lowercase(), rrset.r#type.clone())
+}
+
+fn diff(
+    apex: &str,
+    current: Vec<ResourceRecordSet>,
+    desired: Vec<ResourceRecordSet>,
+    options: &SyncOptions,
+) -> Result<Change> {
+    let ignored = |rrset: &ResourceRecordSet| {
+        !options.manage_apex
+            && (rrset.r#type == RecordType::SOA
+                || (rrset.r#type == RecordType::NS && rrset.name.eq_ignore_ascii_case(apex)))
+    };
+
+    let mut desired_sets = BTreeMap::new();
+    for rrset in desired.into_iter().filter(|rrset| !ignored(rrset)) {
+        if desired_sets.contains_key(&key(&rrset)) {
+            return Err(SyncError::Duplicate {
+                name: rrset.name,
+                r#type: rrset.r#type,
+            }
+            .into());
+        }
+        desired_sets.insert(key(&rrset), rrset);
+    }
+
+    let current: BTreeMap<_, _> = current
+        .into_iter()
+        .filter(|rrset| !ignored(rrset))
+        .map(|rrset| (key(&rrset), rrset))
+        .collect();
+
+    let owned = match &options.ownership_marker {
+        Some(marker) => {
+            add_markers(&mut desired_sets, marker);
+            Some(owned_names(current.values(), marker))
+        }
+        None => None,
+    };
+    let is_owned = |name: &str| match &owned {
+        Some(owned) => owned.contains(name),
+        None => true,
+    };
+
+    // Taking over a name would later delete the record sets other tools keep
+    // there, so names holding anything besides the zone's own apex records
+    // must already be marked.
+    let desired_names: HashSet<_> = desired_sets.keys().map(|(name, _)| name).collect();
+    let is_apex = |rrset: &ResourceRecordSet| {
+        matches!(rrset.r#type, RecordType::SOA | RecordType::NS)
+            && rrset.name.eq_ignore_ascii_case(apex)
+    };
+    for ((name, _), existing) in &current {
+        if desired_names.contains(name) && !is_owned(name) && !is_apex(existing) {
+            return Err(SyncError::NotOwned {
+                name: existing.name.clone(),
+                r#type: existing.r#type.clone(),
+            }
+            .into());
+        }
+    }
+
+    let mut change = Change::default();
+    for (key, rrset) in &desired_sets {
+        match current.get(key) {
+            Some(existing) if !is_owned(&key.0) => {
+                return Err(SyncError::NotOwned {
+                    name: existing.name.clone(),
+                    r#type: existing.r#type.clone(),
+                }
+                .into());
+            }
+            Some(existing) if same_records(existing, rrset) => {}
+            Some(existing) => {
+                change.deletions.push(existing.clone());
+                change.additions.push(rrset.clone());
+            }
+            None => change.additions.push(rrset.clone()),
+        }
+    }
+
+    for (key, rrset) in current {
+        if is_owned(&key.0) && !desired_sets.contains_key(&key) {
+            change.deletions.push(rrset);
+        }
+    }
+
+    Ok(change)
+}
+
+/// Adds the ownership marker to the `TXT` record set of every desired name.
+fn add_markers(desired: &mut BTreeMap<Key, ResourceRecordSet>, marker: &str) {
+    let marker_rrdata = Txt::new(marker).to_string();
+
+    let names: Vec<_> = desired
+        .values()
+        .map(|rrset| (rrset.name.clone(), rrset.ttl.unwrap_or(DEFAULT_MARKER_TTL)))
+        .collect();
+
+    for (name, ttl) in names {
+        let txt = desired
+            .entry((name.to_ascii_lowercase(), RecordType::TXT))
+            .or_insert_with(|| {
+                ResourceRecordSet::new(&name, RecordType::TXT, ttl, Vec::<String>::new())
+            });
+
+        if !txt.rrdatas.iter().any(|rrdata| is_marker(rrdata, marker)) {
+            txt.rrdatas.push(marker_rrdata.clone());
+        }
+    }
+}
+
+const DEFAULT_MARKER_TTL: i32 = 300;
+
+/// The names that have a `TXT` record containing the ownership marker.
+fn owned_names<'a>(
+    current: impl Iterator<Item = &'a ResourceRecordSet>,
+    marker: &str,
+) -> HashSet<String> {
+    current
+        .filter(|rrset| rrset.r#type == RecordType::TXT)
+        .filter(|rrset| rrset.rrdatas.iter().any(|rrdata| is_marker(rrdata, marker)))
+        .map(|rrset| rrset.name.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_marker(rrdata: &str, marker: &str) -> bool {
+    matches!(rrdata.parse::<Txt>(), Ok(txt) if txt.joined() == marker)
+}
+
+fn same_records(current: &ResourceRecordSet, desired: &ResourceRecordSet) -> bool {
+    let mut current_rrdatas = normalized_rrdatas(current);
+    let mut desired_rrdatas = normalized_rrdatas(desired);
+    current_rrdatas.sort();
+    desired_rrdatas.sort();
+
+    current.ttl == desired.ttl
+        && current_rrdatas == desired_rrdatas
+        && current.routing_policy == desired.routing_policy
+}
+
+/// The record data in canonical presentation format, so that eg. `foo` and
+/// `"foo"` compare equal. Record data that doesn't parse is kept as is.
+fn normalized_rrdatas(rrset: &ResourceRecordSet) -> Vec<String> {
+    rrset
+        .rrdatas
+        .iter()
+        .map(|rrdata| match RData::parse(rrset.r#type.clone(), rrdata) {
+            Ok(rdata) => rdata.to_string(),
+            Err(_) => rrdata.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,996 @@
+//! An in-memory fake of the Cloud DNS API, for testing code that uses this
+//! crate without credentials or network access. Requires the `testing`
+//! feature.
+//!
+//! ```
+//! # async fn run() -> cloud_dns::Result<()> {
+//! use cloud_dns::{api::managed_zones::ManagedZone, testing::FakeDnsServer};
+//!
+//! let server = FakeDnsServer::new();
+//! let client = server.client("my-project");
+//!
+//! client
+//!     .managed_zones()
+//!     .create(ManagedZone::public("example", "example.com."))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The fake implements zones, record sets, changes, DNSSEC keys, zone
+//! operations and policies, including pagination and Google's error bodies.
+//! Changes are applied immediately, but report `pending` until they have been
+//! fetched a number of times, see [`FakeDnsServer::pending_polls`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http::{Method, Request, Response, StatusCode};
+use hyper::Body;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use tower::Service;
+
+use crate::{
+    api::{
+        changes::{Change, Status},
+        managed_zones::{ManagedZone, Visibility},
+        policies::Policy,
+        resource_record_sets::ResourceRecordSet,
+    },
+    rdata::RecordType,
+    DnsClient,
+};
+
+/// The time reported for all resources created by the fake.
+const TIMESTAMP: &str = "2024-01-01T00:00:00.000Z";
+
+const NAME_SERVERS: [&str; 4] = [
+    "ns-cloud-a1.googledomains.com.",
+    "ns-cloud-a2.googledomains.com.",
+    "ns-cloud-a3.googledomains.com.",
+    "ns-cloud-a4.googledomains.com.",
+];
+
+/// A [`Service`] implementing the Cloud DNS v1 REST API in memory.
+///
+/// Clones share the same state, so a clone can be kept to inspect or modify
+/// the state while a client uses the server.
+#[derive(Clone, Default)]
+pub struct FakeDnsServer {
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeDnsServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A client for `project_id` that sends its requests to this server,
+    /// without authentication.
+    pub fn client(&self, project_id: &str) -> DnsClient {
+        DnsClient::builder(project_id)
+            .disable_authentication()
+            .build(self.clone())
+            .expect("the project id can be joined onto the default base URL")
+    }
+
+    /// Sets the maximum number of results per page of list calls, so that
+    /// pagination can be tested with few resources. Defaults to 100.
+    pub fn page_size(self, page_size: usize) -> Self {
+        self.lock().page_size = page_size.max(1);
+        self
+    }
+
+    /// Sets how many times a change has to be fetched before it is reported as
+    /// done. Defaults to 0, ie. changes are created as done.
+    pub fn pending_polls(self, polls: u32) -> Self {
+        self.lock().pending_polls = polls;
+        self
+    }
+
+    /// Fails the next request with `status` and an error of `reason`, eg.
+    /// `503` and `backendError`, to test error handling.
+    pub fn fail_next_request(&self, status: StatusCode, reason: &str) {
+        self.lock().injected_errors.push(ApiError::new(
+            status,
+            reason,
+            "Injected error from the fake server.",
+        ));
+    }
+
+    /// The record sets of the zone with the given name or id, ordered by name
+    /// and type.
+    pub fn record_sets(&self, managed_zone: &str) -> Vec<ResourceRecordSet> {
+        let state = self.lock();
+        state
+            .zone(managed_zone)
+            .map(|zone| zone.rrsets.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            // A panicking test shouldn't fail the other tests using the server.
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Service<Request<Body>> for FakeDnsServer {
+    type Response = Response<Body>;
+    type Error = hyper::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, hyper::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let server = self.clone();
+
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+
+            let result = server.lock().handle(&parts.method, &parts.uri, &body);
+            Ok(into_response(result))
+        })
+    }
+}
+
+type Reply = Result<Option<Value>, ApiError>;
+
+struct ApiError {
+    status: StatusCode,
+    reason: String,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, reason: &str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            reason: reason.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn not_found(what: &str, name: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "notFound",
+            format!("The '{}' resource named '{}' does not exist.", what, name),
+        )
+    }
+
+    fn already_exists(what: &str, name: &str) -> Self {
+        Self::new(
+            StatusCode::CONFLICT,
+            "alreadyExists",
+            format!("The resource '{}' named '{}' already exists", what, name),
+        )
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid", message)
+    }
+}
+
+fn into_response(result: Reply) -> Response<Body> {
+    let (status, body) = match result {
+        Ok(Some(value)) => (StatusCode::OK, Body::from(value.to_string())),
+        Ok(None) => (StatusCode::NO_CONTENT, Body::empty()),
+        Err(error) => {
+            let status = match error.status {
+                StatusCode::BAD_REQUEST => "INVALID_ARGUMENT",
+                StatusCode::FORBIDDEN => "PERMISSION_DENIED",
+                StatusCode::NOT_FOUND => "NOT_FOUND",
+                StatusCode::CONFLICT => "ALREADY_EXISTS",
+                StatusCode::PRECONDITION_FAILED => "FAILED_PRECONDITION",
+                StatusCode::TOO_MANY_REQUESTS => "RESOURCE_EXHAUSTED",
+                StatusCode::SERVICE_UNAVAILABLE => "UNAVAILABLE",
+                _ => "INTERNAL",
+            };
+
+            let body = json!({
+                "error": {
+                    "code": error.status.as_u16(),
+                    "message": error.message,
+                    "errors": [{
+                        "message": error.message,
+                        "domain": "global",
+                        "reason": error.reason,
+                    }],
+                    "status": status,
+                }
+            });
+
+            (error.status, Body::from(body.to_string()))
+        }
+    };
+
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    if status != StatusCode::NO_CONTENT {
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/json; charset=UTF-8"),
+        );
+    }
+
+    response
+}
+
+struct State {
+    page_size: usize,
+    pending_polls: u32,
+    injected_errors: Vec<ApiError>,
+    next_id: u64,
+    zones: BTreeMap<String, Zone>,
+    policies: BTreeMap<String, Value>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            page_size: 100,
+            pending_polls: 0,
+            injected_errors: Vec::new(),
+            // Ids look like the large numbers the API hands out.
+            next_id: 1_000_000_000,
+            zones: BTreeMap::new(),
+            policies: BTreeMap::new(),
+        }
+    }
+}
+
+struct Zone {
+    zone: Value,
+    rrsets: BTreeMap<(String, RecordType), ResourceRecordSet>,
+    changes: Vec<FakeChange>,
+    operations: Vec<Value>,
+    dns_keys: Vec<Value>,
+}
+
+struct FakeChange {
+    change: Change,
+    polls_left: u32,
+}
+
+impl FakeChange {
+    fn to_value(&self) -> Value {
+        let mut change = self.change.clone();
+        change.status = Some(if self.polls_left == 0 {
+            Status::Done
+        } else {
+            Status::Pending
+        });
+        change.is_serving = Some(self.polls_left == 0);
+
+        to_value(&change)
+    }
+}
+
+impl Zone {
+    fn dns_name(&self) -> &str {
+        self.zone["dnsName"].as_str().unwrap_or_default()
+    }
+
+    fn name(&self) -> &str {
+        self.zone["name"].as_str().unwrap_or_default()
+    }
+
+    fn is_apex(&self, rrset: &ResourceRecordSet) -> bool {
+        matches!(rrset.r#type, RecordType::SOA | RecordType::NS)
+            && rrset.name.eq_ignore_ascii_case(self.dns_name())
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), ApiError> {
+        let dns_name = self.dns_name().to_ascii_lowercase();
+        let name = name.to_ascii_lowercase();
+
+        if name == dns_name || name.ends_with(&format!(".{}", dns_name)) {
+            Ok(())
+        } else {
+            Err(ApiError::invalid(format!(
+                "The resource record set name '{}' is not within the zone '{}'.",
+                name, dns_name
+            )))
+        }
+    }
+}
+
+fn key(rrset: &ResourceRecordSet) -> (String, RecordType) {
+    (rrset.name.to_ascii_lowercase(), rrset.r#type.clone())
+}
+
+/// Whether a deletion matches the current record set exactly.
+fn matches(current: &ResourceRecordSet, deletion: &ResourceRecordSet) -> bool {
+    let mut current_rrdatas = current.rrdatas.clone();
+    let mut deleted_rrdatas = deletion.rrdatas.clone();
+    current_rrdatas.sort();
+    deleted_rrdatas.sort();
+
+    current.ttl == deletion.ttl && current_rrdatas == deleted_rrdatas
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("API models serialize to JSON")
+}
+
+fn parse_body<T: DeserializeOwned>(body: &Bytes) -> Result<T, ApiError> {
+    serde_json::from_slice(body)
+        .map_err(|e| ApiError::invalid(format!("Invalid JSON payload received. {}", e)))
+}
+
+/// Replaces the top level fields of `target` with those in `patch`.
+fn merge(target: &mut Value, patch: Value) {
+    if let (Some(target), Value::Object(patch)) = (target.as_object_mut(), patch) {
+        for (key, value) in patch {
+            target.insert(key, value);
+        }
+    }
+}
+
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = segment
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Returns the page of `items` selected by the `pageToken` and `maxResults`
+/// query parameters, and the token of the next page.
+fn paginate(
+    items: Vec<Value>,
+    query: &HashMap<String, String>,
+    page_size: usize,
+) -> Result<(Vec<Value>, Option<String>), ApiError> {
+    let start = match query.get("pageToken") {
+        Some(token) => token
+            .parse::<usize>()
+            .map_err(|_| ApiError::invalid(format!("Invalid value for page token: {}", token)))?,
+        None => 0,
+    };
+    let max_results = match query.get("maxResults") {
+        Some(max) => max
+            .parse::<usize>()
+            .map_err(|_| ApiError::invalid(format!("Invalid value for maxResults: {}", max)))?
+            .clamp(1, page_size),
+        None => page_size,
+    };
+
+    let end = (start + max_results).min(items.len());
+    let next_page_token = if end < items.len() {
+        Some(end.to_string())
+    } else {
+        None
+    };
+
+    Ok((
+        items.into_iter().skip(start).take(max_results).collect(),
+        next_page_token,
+    ))
+}
+
+impl State {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        self.next_id.to_string()
+    }
+
+    fn zone(&self, name_or_id: &str) -> Option<&Zone> {
+        self.zones.get(name_or_id).or_else(|| {
+            self.zones
+                .values()
+                .find(|zone| zone.zone["id"].as_str() == Some(name_or_id))
+        })
+    }
+
+    fn zone_mut(&mut self, name_or_id: &str) -> Result<&mut Zone, ApiError> {
+        let name = self
+            .zone(name_or_id)
+            .map(|zone| zone.name().to_string())
+            .ok_or_else(|| ApiError::not_found("parameters.managedZone", name_or_id))?;
+
+        Ok(self.zones.get_mut(&name).expect("the zone was just found"))
+    }
+
+    fn policy_name(&self, name_or_id: &str) -> Result<String, ApiError> {
+        self.policies
+            .iter()
+            .find(|(name, policy)| {
+                name.as_str() == name_or_id || policy["id"].as_str() == Some(name_or_id)
+            })
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| ApiError::not_found("parameters.policy", name_or_id))
+    }
+
+    fn list(
+        &self,
+        kind: &str,
+        field: &str,
+        items: Vec<Value>,
+        query: &HashMap<String, String>,
+    ) -> Reply {
+        let (items, next_page_token) = paginate(items, query, self.page_size)?;
+
+        let mut response = json!({ "kind": kind, field: items });
+        if let Some(token) = next_page_token {
+            response["nextPageToken"] = Value::from(token);
+        }
+
+        Ok(Some(response))
+    }
+
+    fn handle(&mut self, method: &Method, uri: &http::Uri, body: &Bytes) -> Reply {
+        if !self.injected_errors.is_empty() {
+            return Err(self.injected_errors.remove(0));
+        }
+
+        let segments: Vec<String> = uri
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(percent_decode)
+            .collect();
+        let query: HashMap<String, String> =
+            url::form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+                .into_owned()
+                .collect();
+
+        // Requests look like `/dns/v1/projects/{project}/...`.
+        let project = match segments.iter().position(|segment| segment == "projects") {
+            Some(i) if i + 1 < segments.len() => i + 1,
+            _ => return Err(ApiError::not_found("path", uri.path())),
+        };
+        let project_id = segments[project].clone();
+        let path: Vec<&str> = segments[project + 1..].iter().map(String::as_str).collect();
+
+        match (method, path.as_slice()) {
+            (&Method::GET, []) => Ok(Some(project_json(&project_id))),
+
+            (&Method::GET, ["managedZones"]) => self.list_zones(&query),
+            (&Method::POST, ["managedZones"]) => self.create_zone(body),
+            (&Method::GET, ["managedZones", zone]) => Ok(Some(self.zone_mut(zone)?.zone.clone())),
+            (&Method::PATCH, ["managedZones", zone]) => self.update_zone(zone, body, true),
+            (&Method::PUT, ["managedZones", zone]) => self.update_zone(zone, body, false),
+            (&Method::DELETE, ["managedZones", zone]) => self.delete_zone(zone),
+
+            (&Method::GET, ["managedZones", zone, "rrsets"]) => self.list_rrsets(zone, &query),
+            (&Method::POST, ["managedZones", zone, "rrsets"]) => self.create_rrset(zone, body),
+            (&Method::GET, ["managedZones", zone, "rrsets", name, r#type]) => {
+                let zone = self.zone_mut(zone)?;
+                let rrset = zone
+                    .rrsets
+                    .get(&rrset_key(name, r#type)?)
+                    .ok_or_else(|| ApiError::not_found("parameters.name", name))?;
+
+                Ok(Some(to_value(rrset)))
+            }
+            (&Method::PATCH, ["managedZones", zone, "rrsets", name, r#type]) => {
+                self.patch_rrset(zone, name, r#type, body)
+            }
+            (&Method::DELETE, ["managedZones", zone, "rrsets", name, r#type]) => {
+                let zone = self.zone_mut(zone)?;
+                zone.rrsets
+                    .remove(&rrset_key(name, r#type)?)
+                    .ok_or_else(|| ApiError::not_found("parameters.name", name))?;
+
+                Ok(None)
+            }
+
+            (&Method::GET, ["managedZones", zone, "changes"]) => {
+                let changes = self
+                    .zone_mut(zone)?
+                    .changes
+                    .iter()
+                    .map(FakeChange::to_value)
+                    .collect();
+
+                self.list("dns#changesListResponse", "changes", changes, &query)
+            }
+            (&Method::POST, ["managedZones", zone, "changes"]) => self.create_change(zone, body),
+            (&Method::GET, ["managedZones", zone, "changes", id]) => {
+                let change = self
+                    .zone_mut(zone)?
+                    .changes
+                    .iter_mut()
+                    .find(|change| change.change.id.as_deref() == Some(*id))
+                    .ok_or_else(|| ApiError::not_found("parameters.changeId", id))?;
+                change.polls_left = change.polls_left.saturating_sub(1);
+
+                Ok(Some(change.to_value()))
+            }
+
+            (&Method::GET, ["managedZones", zone, "dnsKeys"]) => {
+                let keys = self.zone_mut(zone)?.dns_keys.clone();
+
+                self.list("dns#dnsKeysListResponse", "dnsKeys", keys, &query)
+            }
+            (&Method::GET, ["managedZones", zone, "dnsKeys", id]) => self
+                .zone_mut(zone)?
+                .dns_keys
+                .iter()
+                .find(|key| key["id"].as_str() == Some(*id))
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| ApiError::not_found("parameters.dnsKeyId", id)),
+
+            (&Method::GET, ["managedZones", zone, "operations"]) => {
+                let operations = self.zone_mut(zone)?.operations.clone();
+
+                self.list(
+                    "dns#managedZoneOperationsListResponse",
+                    "operations",
+                    operations,
+                    &query,
+                )
+            }
+            (&Method::GET, ["managedZones", zone, "operations", id]) => self
+                .zone_mut(zone)?
+                .operations
+                .iter()
+                .find(|operation| operation["id"].as_str() == Some(*id))
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| ApiError::not_found("parameters.operation", id)),
+
+            (&Method::GET, ["policies"]) => {
+                let policies = self.policies.values().cloned().collect();
+
+                self.list("dns#policiesListResponse", "policies", policies, &query)
+            }
+            (&Method::POST, ["policies"]) => self.create_policy(body),
+            (&Method::GET, ["policies", policy]) => {
+                let name = self.policy_name(policy)?;
+
+                Ok(Some(self.policies[&name].clone()))
+            }
+            (&Method::PATCH, ["policies", policy]) => self.update_policy(policy, body, true),
+            (&Method::PUT, ["policies", policy]) => self.update_policy(policy, body, false),
+            (&Method::DELETE, ["policies", policy]) => {
+                let name = self.policy_name(policy)?;
+                self.policies.remove(&name);
+
+                Ok(None)
+            }
+
+            _ => Err(ApiError::new(
+                StatusCode::NOT_FOUND,
+                "notFound",
+                format!(
+                    "{} {} is not supported by the fake server",
+                    method,
+                    uri.path()
+                ),
+            )),
+        }
+    }
+
+    fn list_zones(&self, query: &HashMap<String, String>) -> Reply {
+        let zones = self
+            .zones
+            .values()
+            .filter(|zone| match query.get("dnsName") {
+                Some(dns_name) => zone.dns_name().eq_ignore_ascii_case(dns_name),
+                None => true,
+            })
+            .map(|zone| zone.zone.clone())
+            .collect();
+
+        self.list("dns#managedZonesListResponse", "managedZones", zones, query)
+    }
+
+    fn create_zone(&mut self, body: &Bytes) -> Reply {
+        let mut zone: ManagedZone = parse_body(body)?;
+
+        if zone.name.is_empty() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "required",
+                "Required field 'entity.managedZone.name' not specified",
+            ));
+        }
+        if !zone.dns_name.ends_with('.') {
+            return Err(ApiError::invalid(format!(
+                "Invalid value for 'entity.managedZone.dnsName': '{}'",
+                zone.dns_name
+            )));
+        }
+        if self.zones.contains_key(&zone.name) {
+            return Err(ApiError::already_exists("entity.managedZone", &zone.name));
+        }
+
+        zone.kind = Some("dns#managedZone".to_string());
+        zone.id = Some(self.next_id());
+        zone.creation_time = Some(TIMESTAMP.to_string());
+        zone.name_servers = NAME_SERVERS.iter().map(|ns| ns.to_string()).collect();
+        zone.visibility.get_or_insert(Visibility::Public);
+
+        let soa = ResourceRecordSet::new(
+            &zone.dns_name,
+            RecordType::SOA,
+            21600,
+            vec![format!(
+                "{} cloud-dns-hostmaster.google.com. 1 21600 3600 259200 300",
+                NAME_SERVERS[0]
+            )],
+        );
+        let ns = ResourceRecordSet::new(&zone.dns_name, RecordType::NS, 21600, NAME_SERVERS);
+
+        let mut fake = Zone {
+            zone: to_value(&zone),
+            rrsets: BTreeMap::new(),
+            changes: Vec::new(),
+            operations: Vec::new(),
+            dns_keys: Vec::new(),
+        };
+        fake.rrsets.insert(key(&soa), with_kind(soa));
+        fake.rrsets.insert(key(&ns), with_kind(ns));
+        // Creating a zone is recorded as its first change.
+        fake.changes.push(FakeChange {
+            change: Change {
+                kind: Some("dns#change".to_string()),
+                additions: fake.rrsets.values().cloned().collect(),
+                id: Some("0".to_string()),
+                start_time: Some(TIMESTAMP.to_string()),
+                ..Change::default()
+            },
+            polls_left: 0,
+        });
+        update_dns_keys(&mut fake);
+
+        let value = fake.zone.clone();
+        self.zones.insert(zone.name, fake);
+
+        Ok(Some(value))
+    }
+
+    fn update_zone(&mut self, name_or_id: &str, body: &Bytes, patch: bool) -> Reply {
+        let update: Value = parse_body(body)?;
+        let operation_id = self.next_id();
+        let zone = self.zone_mut(name_or_id)?;
+
+        let old_value = zone.zone.clone();
+        let mut new_value = if patch { old_value.clone() } else { json!({}) };
+        merge(&mut new_value, update);
+        // The identity and output only fields of the zone can't be changed.
+        for field in &[
+            "kind",
+            "id",
+            "name",
+            "dnsName",
+            "creationTime",
+            "nameServers",
+        ] {
+            new_value[*field] = old_value[*field].clone();
+        }
+
+        serde_json::from_value::<ManagedZone>(new_value.clone())
+            .map_err(|e| ApiError::invalid(format!("Invalid managed zone. {}", e)))?;
+        zone.zone = new_value.clone();
+        update_dns_keys(zone);
+
+        let operation = json!({
+            "kind": "dns#operation",
+            "id": operation_id,
+            "startTime": TIMESTAMP,
+            "status": "done",
+            "user": "fake@example.com",
+            "type": "update",
+            "zoneContext": {
+                "oldValue": old_value,
+                "newValue": new_value,
+            },
+        });
+        zone.operations.push(operation.clone());
+
+        Ok(Some(operation))
+    }
+
+    fn delete_zone(&mut self, name_or_id: &str) -> Reply {
+        let zone = self.zone_mut(name_or_id)?;
+
+        if zone.rrsets.values().any(|rrset| !zone.is_apex(rrset)) {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "containerNotEmpty",
+                format!(
+                    "The resource named '{}' cannot be deleted because it is not empty",
+                    zone.name()
+                ),
+            ));
+        }
+
+        let name = zone.name().to_string();
+        self.zones.remove(&name);
+
+        Ok(None)
+    }
+
+    fn list_rrsets(&mut self, name_or_id: &str, query: &HashMap<String, String>) -> Reply {
+        let r#type = match query.get("type") {
+            Some(r#type) => Some(parse_type(r#type)?),
+            None => None,
+        };
+        let name = query.get("name").map(|name| name.to_ascii_lowercase());
+
+        let rrsets = self
+            .zone_mut(name_or_id)?
+            .rrsets
+            .values()
+            .filter(|rrset| name.as_ref().map(|name| key(rrset).0 == *name) != Some(false))
+            .filter(|rrset| r#type.as_ref().map(|r#type| rrset.r#type == *r#type) != Some(false))
+            .map(to_value)
+            .collect();
+
+        self.list(
+            "dns#resourceRecordSetsListResponse",
+            "rrsets",
+            rrsets,
+            query,
+        )
+    }
+
+    fn create_rrset(&mut self, name_or_id: &str, body: &Bytes) -> Reply {
+        let rrset: ResourceRecordSet = parse_body(body)?;
+        let zone = self.zone_mut(name_or_id)?;
+
+        zone.check_name(&rrset.name)?;
+        if zone.rrsets.contains_key(&key(&rrset)) {
+            return Err(ApiError::already_exists("entity.rrset", &rrset.name));
+        }
+
+        let rrset = with_kind(rrset);
+        zone.rrsets.insert(key(&rrset), rrset.clone());
+
+        Ok(Some(to_value(&rrset)))
+    }
+
+    fn patch_rrset(&mut self, name_or_id: &str, name: &str, r#type: &str, body: &Bytes) -> Reply {
+        let key = rrset_key(name, r#type)?;
+        let zone = self.zone_mut(name_or_id)?;
+        let current = zone
+            .rrsets
+            .get_mut(&key)
+            .ok_or_else(|| ApiError::not_found("parameters.name", name))?;
+
+        let mut patched = to_value(&*current);
+        merge(&mut patched, parse_body(body)?);
+        let patched: ResourceRecordSet = serde_json::from_value(patched)
+            .map_err(|e| ApiError::invalid(format!("Invalid resource record set. {}", e)))?;
+
+        if self::key(&patched) != key {
+            return Err(ApiError::invalid(
+                "The name and type of a resource record set can't be changed.",
+            ));
+        }
+        *current = patched.clone();
+
+        Ok(Some(to_value(&patched)))
+    }
+
+    fn create_change(&mut self, name_or_id: &str, body: &Bytes) -> Reply {
+        let change: Change = parse_body(body)?;
+        let pending_polls = self.pending_polls;
+        let zone = self.zone_mut(name_or_id)?;
+
+        if change.additions.is_empty() && change.deletions.is_empty() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "required",
+                "The 'entity.change' resource must have at least one addition or deletion.",
+            ));
+        }
+
+        let mut rrsets = zone.rrsets.clone();
+        for (i, deletion) in change.deletions.iter().enumerate() {
+            match rrsets.remove(&key(deletion)) {
+                Some(current) if matches(&current, deletion) => {}
+                Some(_) => {
+                    return Err(ApiError::new(
+                        StatusCode::PRECONDITION_FAILED,
+                        "conditionNotMet",
+                        format!("Precondition not met for 'entity.change.deletions[{}]'", i),
+                    ))
+                }
+                None => {
+                    return Err(ApiError::not_found(
+                        &format!("entity.change.deletions[{}]", i),
+                        &deletion.name,
+                    ))
+                }
+            }
+        }
+        for (i, addition) in change.additions.iter().enumerate() {
+            zone.check_name(&addition.name)?;
+            // Routed record sets take their rrdatas from the routing policy.
+            if addition.rrdatas.is_empty() && addition.routing_policy.is_none() {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "required",
+                    format!(
+                        "The resource 'entity.change.additions[{}]' must have at least one rrdata",
+                        i
+                    ),
+                ));
+            }
+
+            let addition = with_kind(addition.clone());
+            if rrsets.insert(key(&addition), addition.clone()).is_some() {
+                return Err(ApiError::already_exists(
+                    &format!("entity.change.additions[{}]", i),
+                    &addition.name,
+                ));
+            }
+        }
+        zone.rrsets = rrsets;
+
+        let fake = FakeChange {
+            change: Change {
+                kind: Some("dns#change".to_string()),
+                additions: change.additions.into_iter().map(with_kind).collect(),
+                deletions: change.deletions.into_iter().map(with_kind).collect(),
+                start_time: Some(TIMESTAMP.to_string()),
+                id: Some(zone.changes.len().to_string()),
+                ..Change::default()
+            },
+            polls_left: pending_polls,
+        };
+        let value = fake.to_value();
+        zone.changes.push(fake);
+
+        Ok(Some(value))
+    }
+
+    fn create_policy(&mut self, body: &Bytes) -> Reply {
+        let mut policy: Policy = parse_body(body)?;
+
+        if policy.name.is_empty() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "required",
+                "Required field 'entity.policy.name' not specified",
+            ));
+        }
+        if self.policies.contains_key(&policy.name) {
+            return Err(ApiError::already_exists("entity.policy", &policy.name));
+        }
+
+        policy.kind = Some("dns#policy".to_string());
+        policy.id = Some(self.next_id());
+
+        let value = to_value(&policy);
+        self.policies.insert(policy.name, value.clone());
+
+        Ok(Some(value))
+    }
+
+    fn update_policy(&mut self, name_or_id: &str, body: &Bytes, patch: bool) -> Reply {
+        let update: Value = parse_body(body)?;
+        let name = self.policy_name(name_or_id)?;
+        let policy = self
+            .policies
+            .get_mut(&name)
+            .expect("the policy was just found");
+
+        let mut new_value = if patch { policy.clone() } else { json!({}) };
+        merge(&mut new_value, update);
+        for field in &["kind", "id", "name"] {
+            new_value[*field] = policy[*field].clone();
+        }
+
+        serde_json::from_value::<Policy>(new_value.clone())
+            .map_err(|e| ApiError::invalid(format!("Invalid policy. {}", e)))?;
+        *policy = new_value.clone();
+
+        Ok(Some(json!({ "policy": new_value })))
+    }
+}
+
+fn parse_type(r#type: &str) -> Result<RecordType, ApiError> {
+    r#type
+        .parse()
+        .map_err(|_| ApiError::invalid(format!("Invalid value for 'type': '{}'", r#type)))
+}
+
+fn rrset_key(name: &str, r#type: &str) -> Result<(String, RecordType), ApiError> {
+    Ok((name.to_ascii_lowercase(), parse_type(r#type)?))
+}
+
+fn with_kind(mut rrset: ResourceRecordSet) -> ResourceRecordSet {
+    rrset.kind = Some("dns#resourceRecordSet".to_string());
+    rrset
+}
+
+/// Generates keys for zones with DNSSEC turned on, and removes them once it
+/// is turned off.
+fn update_dns_keys(zone: &mut Zone) {
+    let state = zone.zone["dnssecConfig"]["state"].as_str();
+
+    if state == Some("off") || state.is_none() {
+        zone.dns_keys.clear();
+        return;
+    }
+    if !zone.dns_keys.is_empty() {
+        return;
+    }
+
+    for (id, (r#type, key_length, key_tag)) in
+        [("keySigning", 2048, 20326), ("zoneSigning", 1024, 37291)]
+            .iter()
+            .enumerate()
+    {
+        let mut key = json!({
+            "kind": "dns#dnsKey",
+            "id": id.to_string(),
+            "algorithm": "rsasha256",
+            "keyLength": key_length,
+            "publicKey": "AwEAAaz/tAm8yTn4Mfeh5eyI96WSVexTBAvkMgJzkKTOiW1vkIbzxeF3",
+            "creationTime": TIMESTAMP,
+            "isActive": true,
+            "type": r#type,
+            "keyTag": key_tag,
+        });
+        if *r#type == "keySigning" {
+            key["digests"] = json!([{
+                "type": "sha256",
+                "digest": "E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+            }]);
+        }
+
+        zone.dns_keys.push(key);
+    }
+}
+
+fn project_json(project_id: &str) -> Value {
+    json!({
+        "kind": "dns#project",
+        "number": "123456789012",
+        "id": project_id,
+        "quota": {
+            "kind": "dns#quota",
+            "managedZones": 10000,
+            "rrsetsPerManagedZone": 10000,
+            "rrsetAdditionsPerChange": 1000,
+            "rrsetDeletionsPerChange": 1000,
+            "totalRrdataSizePerChange": 100000,
+            "resourceRecordsPerRrset": 100,
+            "dnsKeysPerManagedZone": 4,
+            "whitelistedKeySpecs": [],
+            "networksPerManagedZone": 1000,
+            "managedZonesPerNetwork": 1000,
+            "policies": 100,
+            "networksPerPolicy": 1000,
+            "targetNameServersPerPolicy": 100,
+            "targetNameServersPerManagedZone": 100,
+        },
+    })
+}
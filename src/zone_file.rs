@@ -0,0 +1,683 @@
+//! Reading and writing of RFC 1035 master files, aka. BIND zone files.
+//!
+//! ```no_run
+//! # async fn run(client: cloud_dns::DnsClient) -> Result<(), Box<dyn std::error::Error>> {
+//! use cloud_dns::{api::changes::Change, zone_file};
+//!
+//! let rrsets = zone_file::parse_file("example.com.zone", "example.com.")?;
+//! client
+//!     .changes()
+//!     .create("example-zone", Change::new(rrsets, Vec::new()))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fmt,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+};
+
+use crate::{
+    api::resource_record_sets::ResourceRecordSet,
+    rdata::{RData, RecordType},
+};
+
+/// An error reading a zone file, with the line it occurred on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneFileError {
+    /// The file the error occurred in, `None` for the input passed to
+    /// [`parse`].
+    pub path: Option<PathBuf>,
+    /// The line the record or directive starts on, counting from 1, or 0 if
+    /// the file couldn't be read.
+    pub line: usize,
+    pub message: String,
+}
+
+impl ZoneFileError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            path: None,
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ZoneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            // Reading the file itself failed.
+            Some(path) if self.line == 0 => write!(f, "{}: {}", path.display(), self.message),
+            Some(path) => write!(f, "{}:{}: {}", path.display(), self.line, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ZoneFileError {}
+
+/// Parses a zone file into record sets, grouped by name and type in the order
+/// they first appear. Record data is normalized to the format of [`RData`].
+///
+/// `origin` is the initial `$ORIGIN`, eg. `example.com.`, which relative
+/// names are qualified with. `$INCLUDE` paths are resolved relative to the
+/// current directory.
+///
+/// Records without a TTL use the last `$TTL`, or else the TTL of the previous
+/// record. Included files start out with the `$TTL` of the including file.
+/// Record sets take the TTL of their first record.
+pub fn parse(input: &str, origin: &str) -> Result<Vec<ResourceRecordSet>, ZoneFileError> {
+    let mut parser = Parser::default();
+    parser.parse(input, &absolute(origin), Path::new(""))?;
+
+    Ok(parser.rrsets)
+}
+
+/// Reads and parses the zone file at `path`, see [`parse`]. `$INCLUDE` paths
+/// are resolved relative to the directory of the including file.
+pub fn parse_file(
+    path: impl AsRef<Path>,
+    origin: &str,
+) -> Result<Vec<ResourceRecordSet>, ZoneFileError> {
+    let mut parser = Parser::default();
+    parser.parse_file(path.as_ref(), &absolute(origin), None, 0)?;
+
+    Ok(parser.rrsets)
+}
+
+/// Renders record sets as a zone file with the given `$ORIGIN`.
+///
+/// The output is canonical, so that exports of the same records can be
+/// diffed: record sets are ordered with the apex first and then by name,
+/// reading labels from right to left, and rrdatas are sorted and normalized
+/// to the format of [`RData`]. Names within the origin are written relative
+/// to it, and the most common TTL becomes the `$TTL`, which record sets
+/// without a TTL take too. If none has a TTL, the `$TTL` is
+/// [`DEFAULT_TTL`]. `RRSIG`s are omitted.
+///
+/// Zone files can't express routing policies, so record sets with one are
+/// listed in a comment at the top instead, without the rrdatas chosen by
+/// their policy.
+pub fn render(origin: &str, rrsets: &[ResourceRecordSet]) -> String {
+    let origin = absolute(origin);
+
+    let mut rrsets: Vec<_> = rrsets.iter().collect();
+    rrsets.sort_by_cached_key(|rrset| sort_key(rrset, &origin));
+
+    // Record sets without a TTL take the `$TTL`, so there must be one for the
+    // output to parse.
+    let default_ttl = match most_common_ttl(&rrsets) {
+        None if rrsets.iter().any(|rrset| rrset.ttl.is_none()) => Some(DEFAULT_TTL),
+        ttl => ttl,
+    };
+
+    let mut lines = Vec::new();
+    for rrset in &rrsets {
+        let name = relative(&rrset.name, &origin);
+        let ttl = match rrset.ttl {
+            Some(ttl) if Some(ttl) != default_ttl => ttl.to_string(),
+            _ => String::new(),
+        };
+
+        let mut rrdatas: Vec<_> = rrset
+            .rrdatas
+            .iter()
+            .map(|rrdata| match RData::parse(rrset.r#type.clone(), rrdata) {
+                Ok(rdata) => rdata.to_string(),
+                Err(_) => rrdata.clone(),
+            })
+            .collect();
+        rrdatas.sort();
+        rrdatas.dedup();
+
+        for rrdata in rrdatas {
+            lines.push((name.clone(), ttl.clone(), rrset.r#type.as_str(), rrdata));
+        }
+    }
+
+    let name_width = lines.iter().map(|line| line.0.len()).max().unwrap_or(0);
+    let ttl_width = lines.iter().map(|line| line.1.len()).max().unwrap_or(0);
+    let type_width = lines.iter().map(|line| line.2.len()).max().unwrap_or(0);
+
+    let mut out = format!("$ORIGIN {}\n", origin);
+    if let Some(ttl) = default_ttl {
+        out.push_str(&format!("$TTL {}\n", ttl));
+    }
+    out.push('\n');
+
+    let routed: Vec<_> = rrsets
+        .iter()
+        .filter(|rrset| rrset.routing_policy.is_some())
+        .collect();
+    if !routed.is_empty() {
+        out.push_str("; Omitted routing policies, which zone files can't express:\n");
+        for rrset in routed {
+            out.push_str(&format!(";   {} {}\n", rrset.name, rrset.r#type));
+        }
+        out.push('\n');
+    }
+
+    for (name, ttl, r#type, rrdata) in lines {
+        let line = format!(
+            "{:name_width$} {:>ttl_width$} IN {:type_width$} {}",
+            name,
+            ttl,
+            r#type,
+            rrdata,
+            name_width = name_width,
+            ttl_width = ttl_width,
+            type_width = type_width,
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+/// The `$TTL` rendered when no record set has a TTL.
+pub const DEFAULT_TTL: i32 = 300;
+
+/// Orders the apex first, then names by their labels from right to left, then
+/// `SOA` and `NS` before other types.
+fn sort_key(rrset: &ResourceRecordSet, origin: &str) -> (bool, Vec<String>, u8, RecordType) {
+    let name = rrset.name.to_ascii_lowercase();
+    let labels = name
+        .trim_end_matches('.')
+        .rsplit('.')
+        .map(str::to_string)
+        .collect();
+    let type_rank = match rrset.r#type {
+        RecordType::SOA => 0,
+        RecordType::NS => 1,
+        _ => 2,
+    };
+
+    (
+        !name.eq_ignore_ascii_case(origin),
+        labels,
+        type_rank,
+        rrset.r#type.clone(),
+    )
+}
+
+/// The TTL used by the most record sets, preferring the lowest on ties.
+fn most_common_ttl(rrsets: &[&ResourceRecordSet]) -> Option<i32> {
+    let mut counts = BTreeMap::new();
+    for ttl in rrsets.iter().filter_map(|rrset| rrset.ttl) {
+        *counts.entry(ttl).or_insert(0) += 1;
+    }
+
+    let max = counts.values().copied().max()?;
+    counts
+        .into_iter()
+        .find(|&(_, count)| count == max)
+        .map(|(ttl, _)| ttl)
+}
+
+/// Writes `name` relative to `origin` if it is within it.
+fn relative(name: &str, origin: &str) -> String {
+    if name.eq_ignore_ascii_case(origin) {
+        return "@".to_string();
+    }
+
+    let suffix = format!(".{}", origin);
+    if origin != "."
+        && name.len() > suffix.len()
+        && name
+            .to_ascii_lowercase()
+            .ends_with(&suffix.to_ascii_lowercase())
+    {
+        return name[..name.len() - suffix.len()].to_string();
+    }
+
+    name.to_string()
+}
+
+/// How deeply `$INCLUDE`s may nest, to catch files including themselves.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Default)]
+struct Parser {
+    rrsets: Vec<ResourceRecordSet>,
+    /// The index of each record set in `rrsets`, by lowercased name and type.
+    index: HashMap<(String, RecordType), usize>,
+}
+
+/// The state that is local to a single file.
+struct FileState<'a> {
+    origin: String,
+    default_ttl: Option<i32>,
+    last_ttl: Option<i32>,
+    last_owner: Option<String>,
+    dir: &'a Path,
+    depth: usize,
+}
+
+impl Parser {
+    /// Parses an included file, which starts out with the `$TTL` of the file
+    /// including it.
+    fn parse_file(
+        &mut self,
+        path: &Path,
+        origin: &str,
+        default_ttl: Option<i32>,
+        depth: usize,
+    ) -> Result<(), ZoneFileError> {
+        let input = std::fs::read_to_string(path).map_err(|e| ZoneFileError {
+            path: Some(path.to_path_buf()),
+            line: 0,
+            message: e.to_string(),
+        })?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut state = FileState {
+            origin: origin.to_string(),
+            default_ttl,
+            last_ttl: None,
+            last_owner: None,
+            dir,
+            depth,
+        };
+
+        self.parse_entries(&input, &mut state).map_err(|mut e| {
+            e.path.get_or_insert_with(|| path.to_path_buf());
+            e
+        })
+    }
+
+    fn parse(&mut self, input: &str, origin: &str, dir: &Path) -> Result<(), ZoneFileError> {
+        let mut state = FileState {
+            origin: origin.to_string(),
+            default_ttl: None,
+            last_ttl: None,
+            last_owner: None,
+            dir,
+            depth: 0,
+        };
+
+        self.parse_entries(input, &mut state)
+    }
+
+    fn parse_entries(
+        &mut self,
+        input: &str,
+        state: &mut FileState<'_>,
+    ) -> Result<(), ZoneFileError> {
+        for entry in lex(input)? {
+            let line = entry.line;
+            let first = &entry.tokens[0];
+
+            if !entry.blank_owner && !first.quoted && first.text.starts_with('$') {
+                self.directive(&entry, state)?;
+            } else {
+                self.record(entry, state)
+                    .map_err(|message| ZoneFileError::new(line, message))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn directive(&mut self, entry: &Entry, state: &mut FileState<'_>) -> Result<(), ZoneFileError> {
+        let line = entry.line;
+        let error = |message: String| ZoneFileError::new(line, message);
+        let args = &entry.tokens[1..];
+        let directive = entry.tokens[0].text.to_ascii_uppercase();
+
+        match (directive.as_str(), args) {
+            ("$ORIGIN", [origin]) => {
+                state.origin = qualify(&origin.text, &state.origin);
+            }
+            ("$TTL", [ttl]) => {
+                state.default_ttl = Some(parse_ttl(&ttl.text).map_err(error)?);
+            }
+            ("$INCLUDE", [file]) | ("$INCLUDE", [file, _]) => {
+                if state.depth >= MAX_INCLUDE_DEPTH {
+                    return Err(error("$INCLUDE nested too deeply".to_string()));
+                }
+
+                let origin = match args.get(1) {
+                    Some(origin) => qualify(&origin.text, &state.origin),
+                    None => state.origin.clone(),
+                };
+
+                self.parse_file(
+                    &state.dir.join(&file.text),
+                    &origin,
+                    state.default_ttl,
+                    state.depth + 1,
+                )?;
+            }
+            ("$ORIGIN", _) | ("$TTL", _) | ("$INCLUDE", _) => {
+                return Err(error(format!(
+                    "invalid number of arguments to {}",
+                    directive
+                )));
+            }
+            _ => return Err(error(format!("unknown directive {}", directive))),
+        }
+
+        Ok(())
+    }
+
+    fn record(&mut self, entry: Entry, state: &mut FileState<'_>) -> Result<(), String> {
+        let mut tokens = entry.tokens.into_iter();
+
+        let owner = if entry.blank_owner {
+            state
+                .last_owner
+                .clone()
+                .ok_or("the first record has no owner name")?
+        } else {
+            let owner = tokens.next().ok_or("missing owner name")?;
+            qualify(&owner.text, &state.origin)
+        };
+
+        // The TTL and class may appear in either order before the type.
+        let mut ttl = None;
+        let r#type = loop {
+            let token = tokens.next().ok_or("missing record type")?;
+
+            if token.text.starts_with(|c: char| c.is_ascii_digit()) && ttl.is_none() {
+                ttl = Some(parse_ttl(&token.text)?);
+            } else if token.text.eq_ignore_ascii_case("IN") {
+                continue;
+            } else if ["CH", "HS", "CS"]
+                .iter()
+                .any(|class| token.text.eq_ignore_ascii_case(class))
+            {
+                return Err(format!("unsupported class {}", token.text));
+            } else {
+                break token
+                    .text
+                    .parse::<RecordType>()
+                    .map_err(|_| format!("unsupported record type {}", token.text))?;
+            }
+        };
+
+        let ttl = match ttl {
+            Some(ttl) => ttl,
+            None => state
+                .default_ttl
+                .or(state.last_ttl)
+                .ok_or("the record has no TTL and no $TTL was set")?,
+        };
+
+        let mut fields: Vec<String> = tokens.map(|token| token.text).collect();
+        if fields.is_empty() {
+            return Err("missing record data".to_string());
+        }
+        qualify_rdata(&r#type, &mut fields, &state.origin)?;
+        let rrdata = RData::parse(r#type.clone(), &fields.join(" "))
+            .map_err(|e| e.to_string())?
+            .to_string();
+
+        state.last_owner = Some(owner.clone());
+        state.last_ttl = Some(ttl);
+        self.add(owner, r#type, ttl, rrdata);
+
+        Ok(())
+    }
+
+    fn add(&mut self, name: String, r#type: RecordType, ttl: i32, rrdata: String) {
+        let key = (name.to_ascii_lowercase(), r#type.clone());
+
+        match self.index.get(&key) {
+            Some(&i) => {
+                let rrset = &mut self.rrsets[i];
+                if !rrset.rrdatas.contains(&rrdata) {
+                    rrset.rrdatas.push(rrdata);
+                }
+            }
+            None => {
+                self.index.insert(key, self.rrsets.len());
+                self.rrsets
+                    .push(ResourceRecordSet::new(&name, r#type, ttl, vec![rrdata]));
+            }
+        }
+    }
+}
+
+/// Qualifies the domain names within record data, and converts the timers of
+/// `SOA` records into seconds.
+fn qualify_rdata(r#type: &RecordType, fields: &mut [String], origin: &str) -> Result<(), String> {
+    let names: &[usize] = match r#type {
+        RecordType::CNAME | RecordType::NS | RecordType::PTR => &[0],
+        RecordType::MX => &[1],
+        RecordType::SRV => &[3],
+        RecordType::NAPTR => &[5],
+        RecordType::SVCB | RecordType::HTTPS => &[1],
+        RecordType::SOA => &[0, 1],
+        // A gateway of type 3 is a domain name.
+        RecordType::IPSECKEY if fields.get(1).map(String::as_str) == Some("3") => &[3],
+        _ => &[],
+    };
+
+    for &i in names {
+        if let Some(field) = fields.get_mut(i) {
+            *field = qualify(field, origin);
+        }
+    }
+
+    if *r#type == RecordType::SOA {
+        for field in fields.iter_mut().skip(3) {
+            *field = parse_ttl(field)?.to_string();
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes `name` fully qualified, relative to `origin`.
+fn qualify(name: &str, origin: &str) -> String {
+    if name == "@" {
+        origin.to_string()
+    } else if is_absolute(name) {
+        name.to_string()
+    } else if origin == "." {
+        format!("{}.", name)
+    } else {
+        format!("{}.{}", name, origin)
+    }
+}
+
+/// Whether `name` ends in an unescaped dot.
+fn is_absolute(name: &str) -> bool {
+    let escapes = name
+        .trim_end_matches('.')
+        .chars()
+        .rev()
+        .take_while(|&c| c == '\\')
+        .count();
+
+    name.ends_with('.') && escapes % 2 == 0
+}
+
+fn absolute(origin: &str) -> String {
+    if is_absolute(origin) {
+        origin.to_string()
+    } else {
+        format!("{}.", origin)
+    }
+}
+
+/// Parses a TTL in seconds, or with BIND's units, eg. `1h30m`.
+fn parse_ttl(s: &str) -> Result<i32, String> {
+    let invalid = || format!("invalid TTL {}", s);
+
+    if let Ok(seconds) = s.parse::<u32>() {
+        return i32::try_from(seconds).map_err(|_| invalid());
+    }
+
+    let mut total: i64 = 0;
+    let mut value: Option<i64> = None;
+    for c in s.chars() {
+        match c.to_digit(10) {
+            Some(digit) => value = Some(value.unwrap_or(0) * 10 + i64::from(digit)),
+            None => {
+                let unit = match c.to_ascii_lowercase() {
+                    's' => 1,
+                    'm' => 60,
+                    'h' => 60 * 60,
+                    'd' => 24 * 60 * 60,
+                    'w' => 7 * 24 * 60 * 60,
+                    _ => return Err(invalid()),
+                };
+                total += value.take().ok_or_else(invalid)? * unit;
+            }
+        }
+
+        if total > i64::from(i32::MAX) || value.unwrap_or(0) > i64::from(i32::MAX) {
+            return Err(invalid());
+        }
+    }
+
+    if value.is_some() {
+        return Err(invalid());
+    }
+
+    i32::try_from(total).map_err(|_| invalid())
+}
+
+/// A record or directive, which may span several lines within parentheses.
+struct Entry {
+    /// The line the entry starts on.
+    line: usize,
+    /// Whether the entry starts with whitespace, ie. reuses the previous
+    /// owner name.
+    blank_owner: bool,
+    tokens: Vec<Lexeme>,
+}
+
+struct Lexeme {
+    /// The token as written, including quotes and escapes.
+    text: String,
+    quoted: bool,
+}
+
+/// Splits the input into entries, dropping comments and parentheses.
+fn lex(input: &str) -> Result<Vec<Entry>, ZoneFileError> {
+    let mut entries = Vec::new();
+    let mut current: Option<Entry> = None;
+    let mut line = 1;
+    let mut depth = 0;
+    let mut line_start = true;
+    let mut leading_blank = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                leading_blank = false;
+
+                if depth == 0 {
+                    entries.extend(current.take());
+                }
+                continue;
+            }
+            ';' => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            c if c.is_whitespace() => {
+                if line_start && current.is_none() {
+                    leading_blank = true;
+                }
+                line_start = false;
+                continue;
+            }
+            _ => {}
+        }
+        line_start = false;
+
+        let entry = current.get_or_insert_with(|| Entry {
+            line,
+            blank_owner: leading_blank,
+            tokens: Vec::new(),
+        });
+
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Err(ZoneFileError::new(line, "unbalanced ')'")),
+            ')' => depth -= 1,
+            '"' => {
+                let mut text = String::new();
+                lex_quoted(&mut chars, &mut text, line)?;
+
+                entry.tokens.push(Lexeme { text, quoted: true });
+            }
+            c => {
+                let mut text = String::from(c);
+                if c == '\\' {
+                    text.extend(chars.next_if(|&c| c != '\n'));
+                }
+
+                // A quote within a token, as in `alpn="h2,h3"`, is part of it.
+                while let Some(c) =
+                    chars.next_if(|&c| !c.is_whitespace() && !matches!(c, ';' | '(' | ')'))
+                {
+                    match c {
+                        '"' => lex_quoted(&mut chars, &mut text, line)?,
+                        '\\' => {
+                            text.push(c);
+                            text.extend(chars.next_if(|&c| c != '\n'));
+                        }
+                        c => text.push(c),
+                    }
+                }
+
+                entry.tokens.push(Lexeme {
+                    text,
+                    quoted: false,
+                });
+            }
+        }
+    }
+
+    if depth > 0 {
+        let line = current.as_ref().map_or(line, |entry| entry.line);
+        return Err(ZoneFileError::new(line, "unclosed '('"));
+    }
+    entries.extend(current);
+
+    // Entries made up of only parentheses have no tokens.
+    entries.retain(|entry| !entry.tokens.is_empty());
+
+    Ok(entries)
+}
+
+/// Appends a quoted string whose opening quote was just read to `text`,
+/// including both quotes and any escapes.
+fn lex_quoted(
+    chars: &mut Peekable<Chars<'_>>,
+    text: &mut String,
+    line: usize,
+) -> Result<(), ZoneFileError> {
+    text.push('"');
+
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => {
+                text.push('\\');
+                text.extend(chars.next_if(|&c| c != '\n'));
+            }
+            Some('\n') | None => {
+                return Err(ZoneFileError::new(line, "unterminated quoted string"))
+            }
+            Some(c) => text.push(c),
+        }
+    }
+    text.push('"');
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! Round trips recorded Cloud DNS API responses through the `api` models, so
+//! that fields which are misnamed or missing from the models are caught.
+
+use cloud_dns::api::{
+    changes::{Change, Changes},
+    dns_keys::DnsKeys,
+    iam::IamPolicy,
+    managed_zone_operations::{ManagedZoneOperation, ManagedZoneOperations},
+    managed_zones::{
+        DnssecState, ForwardingPath, ManagedZone, ManagedZones, NonExistence, Visibility,
+    },
+    policies::Policies,
+    projects::Project,
+    resource_record_sets::{LoadBalancerType, ResourceRecordSets},
+    response_policies::ResponsePolicies,
+    response_policy_rules::{Behavior, ResponsePolicyRules},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Deserializes the fixture into `T` and asserts that serializing it again
+/// produces the same JSON.
+fn round_trip<T: DeserializeOwned + Serialize>(fixture: &str) -> T {
+    let expected: serde_json::Value = serde_json::from_str(fixture).unwrap();
+
+    let de = &mut serde_json::Deserializer::from_str(fixture);
+    let parsed: T = serde_path_to_error::deserialize(de).unwrap();
+
+    assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+
+    parsed
+}
+
+#[test]
+fn managed_zone() {
+    let zone: ManagedZone = round_trip(include_str!("fixtures/managed_zone.json"));
+
+    assert_eq!(zone.dns_name, "example.com.");
+    assert_eq!(zone.name_servers.len(), 4);
+    let dnssec = zone.dnssec_config.unwrap();
+    assert_eq!(dnssec.state, Some(DnssecState::On));
+    assert_eq!(dnssec.non_existence, Some(NonExistence::Nsec3));
+}
+
+#[test]
+fn managed_zones_list() {
+    let zones: ManagedZones = round_trip(include_str!("fixtures/managed_zones_list.json"));
+
+    assert_eq!(zones.managed_zones.len(), 2);
+    let zone = &zones.managed_zones[0];
+    assert_eq!(zone.visibility, Some(Visibility::Private));
+    assert!(zone.cloud_logging_config.as_ref().unwrap().enable_logging);
+    let targets = &zone.forwarding_config.as_ref().unwrap().target_name_servers;
+    assert_eq!(
+        targets[1].ipv6_address.as_deref(),
+        Some("fd20:3a1b:7c2e::53")
+    );
+    assert_eq!(targets[1].forwarding_path, Some(ForwardingPath::Default));
+    assert_eq!(
+        zones.envelope.next_page_token.as_deref(),
+        Some("CkQKQgo-c3RhdGU")
+    );
+}
+
+#[test]
+fn rrsets_list() {
+    let rrsets: ResourceRecordSets = round_trip(include_str!("fixtures/rrsets_list.json"));
+
+    assert_eq!(rrsets.rrsets.len(), 5);
+    assert!(rrsets.envelope.next_page_token.is_none());
+}
+
+#[test]
+fn rrsets_with_routing_policies() {
+    let rrsets: ResourceRecordSets =
+        round_trip(include_str!("fixtures/rrsets_routing_policies.json"));
+
+    let geo = rrsets.rrsets[0]
+        .routing_policy
+        .as_ref()
+        .unwrap()
+        .geo
+        .as_ref()
+        .unwrap();
+    assert!(geo.enable_fencing);
+    let targets = geo.items[1].health_checked_targets.as_ref().unwrap();
+    assert_eq!(
+        targets.internal_load_balancers[0].load_balancer_type,
+        LoadBalancerType::RegionalL4ilb
+    );
+
+    let policy = rrsets.rrsets[2].routing_policy.as_ref().unwrap();
+    assert_eq!(
+        policy.primary_backup.as_ref().unwrap().trickle_traffic,
+        Some(0.1)
+    );
+}
+
+#[test]
+fn change() {
+    let change: Change = round_trip(include_str!("fixtures/change.json"));
+
+    assert_eq!(change.additions.len(), 1);
+    assert_eq!(change.deletions.len(), 1);
+}
+
+#[test]
+fn changes_list() {
+    let changes: Changes = round_trip(include_str!("fixtures/changes_list.json"));
+
+    assert!(changes.changes[0].deletions.is_empty());
+}
+
+#[test]
+fn dns_keys_list() {
+    let keys: DnsKeys = round_trip(include_str!("fixtures/dns_keys_list.json"));
+
+    assert_eq!(keys.dns_keys[0].key_tag, 51033);
+}
+
+#[test]
+fn iam_policy() {
+    let policy: IamPolicy = round_trip(include_str!("fixtures/iam_policy.json"));
+
+    assert_eq!(
+        policy.bindings[1]
+            .condition
+            .as_ref()
+            .unwrap()
+            .title
+            .as_deref(),
+        Some("expires-2030")
+    );
+    assert_eq!(policy.etag.as_deref(), Some("BwXhqDBLkJI="));
+}
+
+#[test]
+fn operation() {
+    let operation: ManagedZoneOperation = round_trip(include_str!("fixtures/operation.json"));
+
+    assert!(operation.zone_context.is_some());
+    assert!(operation.dns_key_context.is_none());
+}
+
+#[test]
+fn operations_list() {
+    let operations: ManagedZoneOperations =
+        round_trip(include_str!("fixtures/operations_list.json"));
+
+    let context = operations.operations[0].dns_key_context.as_ref().unwrap();
+    assert!(context.old_value.is_none());
+}
+
+#[test]
+fn policies_list() {
+    let policies: Policies = round_trip(include_str!("fixtures/policies_list.json"));
+
+    let policy = &policies.policies[0];
+    assert_eq!(policy.enable_inbound_forwarding, Some(true));
+    assert_eq!(policy.networks.len(), 2);
+    assert!(policy.has_network("projects/my-project/global/networks/corp"));
+    let targets = &policy
+        .alternative_name_server_config
+        .as_ref()
+        .unwrap()
+        .target_name_servers;
+    assert_eq!(targets[0].forwarding_path, Some(ForwardingPath::Private));
+    assert_eq!(
+        targets[1].ipv6_address.as_deref(),
+        Some("fd20:3a1b:7c2e::53")
+    );
+
+    let policy = &policies.policies[1];
+    assert_eq!(policy.enable_logging, Some(true));
+    assert!(policy.dns64_config.as_ref().unwrap().scope.all_queries);
+}
+
+#[test]
+fn response_policies_list() {
+    let policies: ResponsePolicies =
+        round_trip(include_str!("fixtures/response_policies_list.json"));
+
+    assert_eq!(policies.response_policies[0].gke_clusters.len(), 1);
+}
+
+#[test]
+fn response_policy_rules_list() {
+    let rules: ResponsePolicyRules =
+        round_trip(include_str!("fixtures/response_policy_rules_list.json"));
+
+    let local_data = rules.response_policy_rules[0].local_data.as_ref().unwrap();
+    assert_eq!(local_data.local_datas[0].rrdatas, ["10.0.0.10"]);
+    assert_eq!(
+        rules.response_policy_rules[1].behavior,
+        Some(Behavior::BypassResponsePolicy)
+    );
+    assert_eq!(rules.next_page_token.as_deref(), Some("CgtwdWJsaWMtd3d3"));
+}
+
+#[test]
+fn project() {
+    let project: Project = round_trip(include_str!("fixtures/project.json"));
+
+    assert_eq!(project.quota.rrset_additions_per_change, 1000);
+}
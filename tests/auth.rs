@@ -0,0 +1,298 @@
+//! Checks that tokens are requested with the configured scopes, cached,
+//! fetched once for concurrent requests and refreshed ahead of expiry.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+use cloud_dns::{
+    auth::{CLOUD_PLATFORM_SCOPE, READ_WRITE_SCOPE},
+    error::DnsError,
+    DnsClient,
+};
+use http::{Request, Response};
+use hyper::Body;
+use tame_oauth::{
+    gcp::{ServiceAccountInfo, ServiceAccountProvider, TokenOrRequest, TokenProvider},
+    Token,
+};
+
+/// Hands out a new token with the given lifetime on every call, counting them
+/// and recording the scopes they were requested with.
+#[derive(Clone)]
+struct CountingProvider {
+    fetches: Arc<AtomicUsize>,
+    scopes: Arc<Mutex<Vec<Vec<String>>>>,
+    lifetime: Option<Duration>,
+    /// How long each fetch blocks, so that concurrent requests overlap.
+    delay: Duration,
+}
+
+impl CountingProvider {
+    fn new(lifetime: Option<Duration>) -> Self {
+        Self {
+            fetches: Arc::new(AtomicUsize::new(0)),
+            scopes: Arc::default(),
+            lifetime,
+            delay: Duration::ZERO,
+        }
+    }
+
+    fn fetches(&self) -> usize {
+        self.fetches.load(Ordering::SeqCst)
+    }
+}
+
+impl TokenProvider for CountingProvider {
+    fn get_token_with_subject<'a, S, I, T>(
+        &self,
+        _subject: Option<T>,
+        scopes: I,
+    ) -> Result<TokenOrRequest, tame_oauth::Error>
+    where
+        S: AsRef<str> + 'a,
+        I: IntoIterator<Item = &'a S>,
+        T: Into<String>,
+    {
+        std::thread::sleep(self.delay);
+        self.scopes.lock().unwrap().push(
+            scopes
+                .into_iter()
+                .map(|scope| scope.as_ref().to_string())
+                .collect(),
+        );
+        let fetch = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+
+        Ok(TokenOrRequest::Token(Token {
+            access_token: format!("token-{}", fetch),
+            refresh_token: String::new(),
+            token_type: "Bearer".to_string(),
+            expires_in: self.lifetime.map(|lifetime| lifetime.as_secs() as i64),
+            expires_in_timestamp: self.lifetime.map(|lifetime| SystemTime::now() + lifetime),
+        }))
+    }
+
+    fn parse_token_response<S>(
+        &self,
+        _hash: u64,
+        _response: http::Response<S>,
+    ) -> Result<Token, tame_oauth::Error>
+    where
+        S: AsRef<[u8]>,
+    {
+        unreachable!("tokens are returned directly")
+    }
+}
+
+/// A client whose requests record their `Authorization` header, and whose
+/// token requests are answered with tokens that expire in two minutes.
+fn client<P>(provider: P) -> (DnsClient, Arc<Mutex<Vec<String>>>, Arc<AtomicUsize>)
+where
+    P: TokenProvider + Send + Sync + 'static,
+{
+    client_with(DnsClient::builder("my-project").token_provider(provider))
+}
+
+/// Like [`client`], for a builder with its authentication already set up.
+fn client_with(
+    builder: cloud_dns::DnsClientBuilder,
+) -> (DnsClient, Arc<Mutex<Vec<String>>>, Arc<AtomicUsize>) {
+    let authorizations = Arc::new(Mutex::new(Vec::new()));
+    let token_requests = Arc::new(AtomicUsize::new(0));
+
+    let service = {
+        let authorizations = authorizations.clone();
+        let token_requests = token_requests.clone();
+
+        tower::service_fn(move |request: Request<Body>| {
+            let authorizations = authorizations.clone();
+            let token_requests = token_requests.clone();
+
+            async move {
+                if request.uri().path() == "/token" {
+                    let fetch = token_requests.fetch_add(1, Ordering::SeqCst) + 1;
+                    let body = format!(
+                        r#"{{"access_token": "sa-token-{}", "token_type": "Bearer", "expires_in": 120}}"#,
+                        fetch
+                    );
+
+                    return Ok::<_, hyper::Error>(Response::new(Body::from(body)));
+                }
+
+                if let Some(authorization) = request.headers().get(http::header::AUTHORIZATION) {
+                    authorizations
+                        .lock()
+                        .unwrap()
+                        .push(authorization.to_str().unwrap().to_string());
+                }
+
+                Ok(Response::new(Body::from(include_str!(
+                    "fixtures/project.json"
+                ))))
+            }
+        })
+    };
+
+    let client = builder.build(service).unwrap();
+
+    (client, authorizations, token_requests)
+}
+
+/// Waits for background refreshes to finish.
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+}
+
+#[tokio::test]
+async fn requests_the_read_write_scope_by_default() {
+    let provider = CountingProvider::new(None);
+    let (client, _, _) = client(provider.clone());
+
+    client.projects().get().await.unwrap();
+
+    assert_eq!(
+        *provider.scopes.lock().unwrap(),
+        vec![vec![READ_WRITE_SCOPE]]
+    );
+}
+
+#[tokio::test]
+async fn requests_custom_scopes() {
+    let provider = CountingProvider::new(None);
+    let (client, authorizations, _) = client_with(
+        DnsClient::builder("my-project")
+            .token_provider(provider.clone())
+            .scopes([CLOUD_PLATFORM_SCOPE, "https://example.com/extra"]),
+    );
+
+    client.projects().get().await.unwrap();
+
+    assert_eq!(
+        *provider.scopes.lock().unwrap(),
+        vec![vec![CLOUD_PLATFORM_SCOPE, "https://example.com/extra"]]
+    );
+    assert_eq!(*authorizations.lock().unwrap(), vec!["Bearer token-1"]);
+}
+
+#[tokio::test]
+async fn missing_credentials() {
+    // Point the default provider at empty locations, so it finds nothing.
+    let config = std::env::temp_dir().join("cloud-dns-missing-credentials");
+    std::env::remove_var("GOOGLE_APPLICATION_CREDENTIALS");
+    std::env::set_var("CLOUDSDK_CONFIG", &config);
+
+    let (client, authorizations, _) = client_with(DnsClient::builder("my-project"));
+
+    let error = client.projects().get().await.unwrap_err();
+
+    assert!(
+        matches!(error, DnsError::MissingCredentials),
+        "unexpected error {:?}",
+        error
+    );
+    assert!(authorizations.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn caches_tokens() {
+    let provider = CountingProvider::new(Some(Duration::from_secs(60 * 60)));
+    let (client, authorizations, _) = client(provider.clone());
+
+    for _ in 0..3 {
+        client.projects().get().await.unwrap();
+    }
+    settle().await;
+
+    assert_eq!(provider.fetches(), 1);
+    assert_eq!(*authorizations.lock().unwrap(), vec!["Bearer token-1"; 3]);
+}
+
+#[tokio::test]
+async fn caches_tokens_without_expiry() {
+    let provider = CountingProvider::new(None);
+    let (client, _, _) = client(provider.clone());
+
+    for _ in 0..3 {
+        client.projects().get().await.unwrap();
+    }
+    settle().await;
+
+    assert_eq!(provider.fetches(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_requests_share_a_fetch() {
+    let mut provider = CountingProvider::new(Some(Duration::from_secs(60 * 60)));
+    provider.delay = Duration::from_millis(50);
+    let (client, authorizations, _) = client(provider.clone());
+
+    let requests = (0..8).map(|_| {
+        let client = client.clone();
+        tokio::spawn(async move { client.projects().get().await })
+    });
+    for request in futures::future::join_all(requests).await {
+        request.unwrap().unwrap();
+    }
+
+    assert_eq!(provider.fetches(), 1);
+    assert_eq!(authorizations.lock().unwrap().len(), 8);
+}
+
+#[tokio::test]
+async fn refreshes_once_ahead_of_expiry() {
+    let provider = CountingProvider::new(Some(Duration::from_secs(2 * 60)));
+    let (client, authorizations, _) = client(provider.clone());
+
+    // The first token is already within the refresh window, so the second
+    // request starts a single refresh while still using it.
+    client.projects().get().await.unwrap();
+    client.projects().get().await.unwrap();
+    settle().await;
+    assert_eq!(provider.fetches(), 2);
+
+    // The refreshed token is also close to expiry, but another refresh isn't
+    // started right away.
+    client.projects().get().await.unwrap();
+    client.projects().get().await.unwrap();
+    settle().await;
+    assert_eq!(provider.fetches(), 2);
+
+    assert_eq!(
+        *authorizations.lock().unwrap(),
+        vec![
+            "Bearer token-1",
+            "Bearer token-1",
+            "Bearer token-2",
+            "Bearer token-2"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn refreshes_service_account_tokens_ahead_of_expiry() {
+    let info =
+        ServiceAccountInfo::deserialize(include_str!("fixtures/service_account.json")).unwrap();
+    let provider = ServiceAccountProvider::new(info).unwrap();
+    let (client, authorizations, token_requests) = client(provider);
+
+    // The service account provider would hand out its cached token until it
+    // expires, so the refresh must bypass it to get a new one.
+    client.projects().get().await.unwrap();
+    client.projects().get().await.unwrap();
+    settle().await;
+    client.projects().get().await.unwrap();
+
+    assert_eq!(token_requests.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        *authorizations.lock().unwrap(),
+        vec![
+            "Bearer sa-token-1",
+            "Bearer sa-token-1",
+            "Bearer sa-token-2"
+        ]
+    );
+}
@@ -0,0 +1,73 @@
+//! Checks that the builder's endpoint and authentication settings are applied
+//! to requests.
+
+use std::sync::{Arc, Mutex};
+
+use cloud_dns::DnsClient;
+use http::{Request, Response};
+use hyper::Body;
+
+/// A client whose requests are recorded and answered with `body`.
+fn client(
+    builder: cloud_dns::DnsClientBuilder,
+    body: &'static str,
+) -> (DnsClient, Arc<Mutex<Vec<Request<Body>>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let service = {
+        let requests = requests.clone();
+
+        tower::service_fn(move |request: Request<Body>| {
+            requests.lock().unwrap().push(request);
+            async move { Ok::<_, hyper::Error>(Response::new(Body::from(body))) }
+        })
+    };
+
+    (builder.build(service).unwrap(), requests)
+}
+
+#[tokio::test]
+async fn uses_base_url_and_api_version() {
+    let (client, requests) = client(
+        DnsClient::builder("my-project")
+            .base_url("http://localhost:8080/emulator".parse().unwrap())
+            .api_version("v1beta2")
+            .disable_authentication(),
+        include_str!("fixtures/managed_zone.json"),
+    );
+
+    client
+        .managed_zones()
+        .get("my-zone".to_string())
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].uri(),
+        "http://localhost:8080/emulator/dns/v1beta2/projects/my-project/managedZones/my-zone"
+    );
+    assert!(!requests[0]
+        .headers()
+        .contains_key(http::header::AUTHORIZATION));
+}
+
+#[tokio::test]
+async fn defaults_to_the_v1_api() {
+    let (client, requests) = client(
+        DnsClient::builder("my-project").disable_authentication(),
+        include_str!("fixtures/project.json"),
+    );
+
+    client.projects().get().await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0].uri(),
+        "https://dns.googleapis.com/dns/v1/projects/my-project"
+    );
+    assert!(!requests[0]
+        .headers()
+        .contains_key(http::header::AUTHORIZATION));
+}
@@ -0,0 +1,334 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use cloud_dns::{
+    api::{
+        changes::{Change, Status},
+        resource_record_sets::{
+            GeoPolicy, GeoPolicyItem, HealthCheckTargets, IpProtocol, LoadBalancerTarget,
+            LoadBalancerType, RRSetRoutingPolicy, ResourceRecordSet, WrrPolicyItem,
+        },
+    },
+    poll::PollOptions,
+    rdata::RecordType,
+    DnsClient,
+};
+use http::{Method, Request, Response};
+use hyper::Body;
+
+/// A client that accepts a change as pending, and reports it as done once it
+/// has been fetched `polls_until_done` times.
+fn client(polls_until_done: usize) -> (DnsClient, Arc<AtomicUsize>) {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let counter = polls.clone();
+
+    let service = tower::service_fn(move |request: Request<Body>| {
+        let counter = counter.clone();
+
+        async move {
+            let status = if request.method() == Method::POST {
+                "pending"
+            } else if counter.fetch_add(1, Ordering::SeqCst) + 1 >= polls_until_done {
+                "done"
+            } else {
+                "pending"
+            };
+
+            Response::builder().body(Body::from(format!(
+                r#"{{"kind": "dns#change", "id": "7", "status": "{}"}}"#,
+                status
+            )))
+        }
+    });
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    (client, polls)
+}
+
+fn options() -> PollOptions {
+    PollOptions::default()
+        .initial_interval(Duration::from_millis(1))
+        .max_interval(Duration::from_millis(5))
+}
+
+#[tokio::test]
+async fn create_and_wait() {
+    let (client, polls) = client(3);
+
+    let change = Change::default().addition(ResourceRecordSet::new(
+        "www.example.com.",
+        RecordType::A,
+        300,
+        vec!["192.0.2.1"],
+    ));
+
+    let change = client
+        .changes()
+        .create_and_wait("example-com", change, &options())
+        .await
+        .unwrap();
+
+    assert_eq!(change.status, Some(Status::Done));
+    assert_eq!(polls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn wait_times_out() {
+    let (client, _) = client(usize::MAX);
+
+    let error = client
+        .changes()
+        .wait_until_done(
+            "example-com",
+            "7",
+            &options().timeout(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(error.is_timeout());
+}
+
+#[tokio::test]
+async fn wait_fetches_before_backing_off() {
+    let (client, polls) = client(1);
+
+    let change = client
+        .changes()
+        .wait_until_done(
+            "example-com",
+            "7",
+            &options()
+                .initial_interval(Duration::from_secs(60 * 60))
+                .timeout(Duration::from_secs(1)),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(change.status, Some(Status::Done));
+    assert_eq!(polls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn wait_cuts_slow_fetches_short() {
+    let service = tower::service_fn(|_: Request<Body>| async {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Response::builder().body(Body::from(r#"{"kind": "dns#change", "status": "done"}"#))
+    });
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    let error = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.changes().wait_until_done(
+            "example-com",
+            "7",
+            &options().timeout(Duration::from_millis(50)),
+        ),
+    )
+    .await
+    .expect("the fetch is cut short by the poll timeout")
+    .unwrap_err();
+
+    assert!(error.is_timeout());
+}
+
+#[tokio::test]
+async fn create_round_trips_routing_policies() {
+    // Echoes the created change back, as Cloud DNS does.
+    let service = tower::service_fn(|request: Request<Body>| async move {
+        let body = hyper::body::to_bytes(request.into_body()).await?;
+        Ok::<_, hyper::Error>(Response::new(Body::from(body)))
+    });
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    let ilb = LoadBalancerTarget {
+        kind: None,
+        load_balancer_type: LoadBalancerType::RegionalL4ilb,
+        ip_address: "10.128.0.5".to_string(),
+        port: "80".to_string(),
+        ip_protocol: IpProtocol::Tcp,
+        network_url:
+            "https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default"
+                .to_string(),
+        project: "my-project".to_string(),
+        region: Some("us-east1".to_string()),
+    };
+    let additions = vec![
+        ResourceRecordSet::routed(
+            "geo.example.com.",
+            RecordType::A,
+            300,
+            RRSetRoutingPolicy::geo(vec![
+                GeoPolicyItem::new("europe-west1", vec!["192.0.2.1"]),
+                GeoPolicyItem::health_checked(
+                    "us-east1",
+                    HealthCheckTargets::internal_load_balancers(vec![ilb.clone()]),
+                ),
+            ]),
+        ),
+        ResourceRecordSet::routed(
+            "wrr.example.com.",
+            RecordType::A,
+            300,
+            RRSetRoutingPolicy::wrr(vec![
+                WrrPolicyItem::new(3.0, vec!["192.0.2.10"]),
+                WrrPolicyItem::new(1.0, vec!["192.0.2.20"]),
+            ]),
+        ),
+        ResourceRecordSet::routed(
+            "failover.example.com.",
+            RecordType::A,
+            30,
+            RRSetRoutingPolicy::primary_backup(
+                HealthCheckTargets::internal_load_balancers(vec![ilb]),
+                GeoPolicy::default().enable_fencing(true),
+            ),
+        ),
+    ];
+
+    let change = client
+        .changes()
+        .create("example-com", Change::new(additions.clone(), Vec::new()))
+        .await
+        .unwrap();
+
+    assert_eq!(change.additions, additions);
+}
+
+#[test]
+fn plan_pairs_deletions_and_additions() {
+    let change = Change::new(
+        vec![
+            ResourceRecordSet::new(
+                "www.example.com.",
+                RecordType::A,
+                60,
+                vec!["192.0.2.1", "192.0.2.3"],
+            ),
+            ResourceRecordSet::new(
+                "new.example.com.",
+                RecordType::CNAME,
+                300,
+                vec!["www.example.com."],
+            ),
+        ],
+        vec![
+            ResourceRecordSet::new(
+                "www.example.com.",
+                RecordType::A,
+                300,
+                vec!["192.0.2.1", "192.0.2.2"],
+            ),
+            ResourceRecordSet::new("old.example.com.", RecordType::A, 300, vec!["192.0.2.4"]),
+        ],
+    );
+
+    let plan = change.plan();
+
+    assert_eq!(
+        plan.to_string(),
+        "\
++ new.example.com. CNAME (ttl 300)
+    + www.example.com.
+- old.example.com. A (ttl 300)
+    - 192.0.2.4
+~ www.example.com. A (ttl 300 -> 60)
+      192.0.2.1
+    - 192.0.2.2
+    + 192.0.2.3
+
+Plan: 1 to add, 1 to change, 1 to delete."
+    );
+
+    let json = serde_json::to_value(&plan).unwrap();
+    assert_eq!(
+        json["summary"],
+        serde_json::json!({"add": 1, "update": 1, "delete": 1})
+    );
+    assert_eq!(
+        json["changes"][2],
+        serde_json::json!({
+            "action": "update",
+            "name": "www.example.com.",
+            "type": "A",
+            "before": {"ttl": 300, "rrdatas": ["192.0.2.1", "192.0.2.2"]},
+            "after": {"ttl": 60, "rrdatas": ["192.0.2.1", "192.0.2.3"]},
+        })
+    );
+}
+
+#[test]
+fn plan_shows_routing_policy_changes() {
+    let routed = |weight| {
+        ResourceRecordSet::routed(
+            "wrr.example.com.",
+            RecordType::A,
+            300,
+            RRSetRoutingPolicy::wrr(vec![
+                WrrPolicyItem::new(weight, vec!["192.0.2.10"]),
+                WrrPolicyItem::new(1.0, vec!["192.0.2.20"]),
+            ]),
+        )
+    };
+    let change = Change::new(vec![routed(1.0)], vec![routed(3.0)]);
+
+    let plan = change.plan();
+
+    assert_eq!(
+        plan.to_string(),
+        r#"~ wrr.example.com. A (ttl 300)
+    - routing policy {"wrr":{"items":[{"weight":3.0,"rrdatas":["192.0.2.10"]},{"weight":1.0,"rrdatas":["192.0.2.20"]}]}}
+    + routing policy {"wrr":{"items":[{"weight":1.0,"rrdatas":["192.0.2.10"]},{"weight":1.0,"rrdatas":["192.0.2.20"]}]}}
+
+Plan: 0 to add, 1 to change, 0 to delete."#
+    );
+
+    let json = serde_json::to_value(&plan).unwrap();
+    assert_eq!(
+        json["changes"][0]["after"]["routingPolicy"]["wrr"]["items"][0]["weight"],
+        1.0
+    );
+}
+
+#[test]
+fn plan_keeps_duplicate_deletions() {
+    let change = Change::new(
+        vec![ResourceRecordSet::new(
+            "www.example.com.",
+            RecordType::A,
+            300,
+            vec!["192.0.2.3"],
+        )],
+        vec![
+            ResourceRecordSet::new("www.example.com.", RecordType::A, 300, vec!["192.0.2.1"]),
+            ResourceRecordSet::new("WWW.example.com.", RecordType::A, 300, vec!["192.0.2.2"]),
+        ],
+    );
+
+    assert_eq!(
+        change.plan().to_string(),
+        "\
+~ www.example.com. A (ttl 300)
+    - 192.0.2.1
+    + 192.0.2.3
+- WWW.example.com. A (ttl 300)
+    - 192.0.2.2
+
+Plan: 0 to add, 1 to change, 1 to delete."
+    );
+}
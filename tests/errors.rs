@@ -0,0 +1,156 @@
+//! Checks that error responses from the API are surfaced as typed errors.
+
+use cloud_dns::{
+    error::{CloudDnsError, DnsError, ErrorKind},
+    DnsClient,
+};
+use http::{Request, Response, StatusCode};
+use hyper::Body;
+
+/// Parses the `error` of a Google error envelope.
+fn parse(body: &str) -> CloudDnsError {
+    let envelope: serde_json::Value = serde_json::from_str(body).unwrap();
+    serde_json::from_value(envelope["error"].clone()).unwrap()
+}
+
+#[test]
+fn parses_error_envelope() {
+    let error = parse(include_str!("fixtures/error_not_found.json"));
+
+    assert_eq!(error.code, 404);
+    assert_eq!(error.status.as_deref(), Some("NOT_FOUND"));
+    assert_eq!(error.errors[0].reason.as_deref(), Some("notFound"));
+    assert_eq!(error.errors[0].domain.as_deref(), Some("global"));
+    assert_eq!(
+        error.to_string(),
+        "Error 404: The 'parameters.managedZone' resource named 'missing-zone' does not exist. (NOT_FOUND)"
+    );
+
+    assert!(matches!(DnsError::from(error), DnsError::Dns { .. }));
+}
+
+#[test]
+fn classifies_by_reason_before_status() {
+    let error = parse(
+        r#"{"error": {"code": 403, "message": "Rate Limit Exceeded", "errors": [{"reason": "rateLimitExceeded", "domain": "usageLimits"}]}}"#,
+    );
+    assert_eq!(error.kind(), ErrorKind::RateLimited);
+
+    let error = DnsError::from(error);
+    assert!(error.is_rate_limited());
+    assert!(error.is_retryable());
+    assert!(!error.is_auth());
+
+    let error = DnsError::from(parse(
+        r#"{"error": {"code": 403, "message": "Forbidden", "errors": [{"reason": "forbidden"}]}}"#,
+    ));
+    assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    assert!(error.is_auth());
+    assert!(!error.is_retryable());
+}
+
+#[test]
+fn classifies_by_status_without_reason() {
+    let kind = |code: u16| {
+        parse(&format!(
+            r#"{{"error": {{"code": {}, "message": ""}}}}"#,
+            code
+        ))
+        .kind()
+    };
+
+    assert_eq!(kind(404), ErrorKind::NotFound);
+    assert_eq!(kind(409), ErrorKind::Conflict);
+    assert_eq!(kind(412), ErrorKind::PreconditionFailed);
+    assert_eq!(kind(429), ErrorKind::RateLimited);
+    assert_eq!(kind(503), ErrorKind::Unavailable);
+    assert!(kind(503).is_retryable());
+    assert_eq!(kind(418), ErrorKind::Other);
+}
+
+/// A client whose every request is answered with `status` and `body`.
+fn client(status: StatusCode, body: &'static str) -> DnsClient {
+    let service = tower::service_fn(move |_request: Request<Body>| async move {
+        Response::builder()
+            .status(status)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+    });
+
+    DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn not_found() {
+    let client = client(
+        StatusCode::NOT_FOUND,
+        include_str!("fixtures/error_not_found.json"),
+    );
+
+    let error = client
+        .managed_zones()
+        .get("missing-zone".to_string())
+        .await
+        .unwrap_err();
+
+    match error {
+        DnsError::Dns { source } => {
+            assert_eq!(source.code, 404);
+            assert_eq!(source.status.as_deref(), Some("NOT_FOUND"));
+            assert_eq!(source.reasons().collect::<Vec<_>>(), ["notFound"]);
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn unparseable_error_body() {
+    let client = client(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>");
+
+    let error = client.policies().get("policy").await.unwrap_err();
+
+    match error {
+        DnsError::Dns { source } => {
+            assert_eq!(source.code, 502);
+            assert_eq!(source.message, "<html>Bad Gateway</html>");
+            assert!(source.errors.is_empty());
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn classifies_client_errors() {
+    let error = client(
+        StatusCode::NOT_FOUND,
+        include_str!("fixtures/error_not_found.json"),
+    )
+    .managed_zones()
+    .get("missing-zone".to_string())
+    .await
+    .unwrap_err();
+    assert!(error.is_not_found());
+    assert!(!error.is_retryable());
+
+    let error = client(
+        StatusCode::FORBIDDEN,
+        r#"{"error": {"code": 403, "message": "Rate Limit Exceeded", "errors": [{"reason": "rateLimitExceeded", "domain": "usageLimits"}]}}"#,
+    )
+    .policies()
+    .get("policy")
+    .await
+    .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::RateLimited);
+    assert!(error.is_retryable());
+    assert!(!error.is_auth());
+
+    let error = client(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>")
+        .policies()
+        .get("policy")
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Unavailable);
+}
@@ -0,0 +1,210 @@
+use std::sync::{Arc, Mutex};
+
+use cloud_dns::{
+    api::iam::{Binding, Condition, IamPolicy},
+    error::DnsError,
+    DnsClient,
+};
+use http::{Request, Response, StatusCode};
+use hyper::Body;
+use serde_json::{json, Value};
+
+const ABORTED: &str = r#"{"error": {"code": 409, "message": "There were concurrent policy changes. Please retry the whole read-modify-write with exponential backoff.", "status": "ABORTED"}}"#;
+
+/// The IAM policy of a zone, whose etag is bumped by every write.
+struct Zone {
+    policy: IamPolicy,
+    version: u32,
+    /// The number of upcoming writes that lose against a concurrent one.
+    concurrent_writes: usize,
+    requests: Vec<(String, Value)>,
+}
+
+fn client(policy: IamPolicy, concurrent_writes: usize) -> (DnsClient, Arc<Mutex<Zone>>) {
+    let zone = Arc::new(Mutex::new(Zone {
+        policy: IamPolicy {
+            etag: Some("etag-0".to_string()),
+            ..policy
+        },
+        version: 0,
+        concurrent_writes,
+        requests: Vec::new(),
+    }));
+    let state = zone.clone();
+
+    let service = tower::service_fn(move |request: Request<Body>| {
+        let state = state.clone();
+
+        async move {
+            let path = request.uri().path().to_string();
+            let body = hyper::body::to_bytes(request.into_body()).await?;
+            let body: Value = serde_json::from_slice(&body).unwrap();
+
+            let mut zone = state.lock().unwrap();
+            zone.requests.push((path.clone(), body.clone()));
+
+            let response = if path.ends_with(":getIamPolicy") {
+                serde_json::to_string(&zone.policy).unwrap()
+            } else if path.ends_with(":setIamPolicy") {
+                let policy: IamPolicy = serde_json::from_value(body["policy"].clone()).unwrap();
+
+                if zone.concurrent_writes > 0 {
+                    zone.concurrent_writes -= 1;
+                    zone.version += 1;
+                    zone.policy.etag = Some(format!("etag-{}", zone.version));
+                }
+                if policy.etag != zone.policy.etag {
+                    return Ok::<_, hyper::Error>(
+                        Response::builder()
+                            .status(StatusCode::CONFLICT)
+                            .body(Body::from(ABORTED))
+                            .unwrap(),
+                    );
+                }
+
+                zone.version += 1;
+                zone.policy = IamPolicy {
+                    etag: Some(format!("etag-{}", zone.version)),
+                    ..policy
+                };
+                serde_json::to_string(&zone.policy).unwrap()
+            } else {
+                json!({"permissions": ["dns.resourceRecordSets.list"]}).to_string()
+            };
+
+            Ok(Response::new(Body::from(response)))
+        }
+    });
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    (client, zone)
+}
+
+fn policy() -> IamPolicy {
+    IamPolicy {
+        version: 3,
+        bindings: vec![
+            Binding::new("roles/dns.admin", vec!["group:dns-admins@example.com"]),
+            Binding::new("roles/dns.reader", vec!["user:bob@example.com"]).condition(
+                Condition::new(
+                    "expires-2030",
+                    r#"request.time < timestamp("2030-01-01T00:00:00Z")"#,
+                ),
+            ),
+        ],
+        ..IamPolicy::default()
+    }
+}
+
+#[tokio::test]
+async fn adds_member_to_role() {
+    let (client, zone) = client(policy(), 0);
+
+    let updated = client
+        .managed_zones()
+        .add_iam_member("team-a", "roles/dns.admin", "group:team-a@example.com")
+        .await
+        .unwrap();
+    assert_eq!(
+        updated.members("roles/dns.admin").collect::<Vec<_>>(),
+        ["group:dns-admins@example.com", "group:team-a@example.com"]
+    );
+
+    {
+        let zone = zone.lock().unwrap();
+        let (path, body) = &zone.requests[0];
+        assert_eq!(
+            path,
+            "/dns/v1/projects/my-project/managedZones/team-a:getIamPolicy"
+        );
+        assert_eq!(body["options"]["requestedPolicyVersion"], 3);
+
+        // The policy is written back with the etag it was read with.
+        let (path, body) = &zone.requests[1];
+        assert!(path.ends_with("/managedZones/team-a:setIamPolicy"));
+        assert_eq!(body["policy"]["etag"], "etag-0");
+        assert_eq!(body["policy"]["version"], 3);
+    }
+
+    // Adding it again doesn't write the policy.
+    client
+        .managed_zones()
+        .add_iam_member("team-a", "roles/dns.admin", "group:team-a@example.com")
+        .await
+        .unwrap();
+    assert_eq!(zone.lock().unwrap().requests.len(), 3);
+}
+
+#[tokio::test]
+async fn removes_member_from_role() {
+    let (client, zone) = client(policy(), 0);
+
+    let updated = client
+        .managed_zones()
+        .remove_iam_member("team-a", "roles/dns.admin", "group:dns-admins@example.com")
+        .await
+        .unwrap();
+
+    // The empty binding is dropped, the conditional one is kept.
+    assert_eq!(updated.bindings.len(), 1);
+    assert_eq!(updated.bindings[0].role, "roles/dns.reader");
+
+    // Removing a conditional grant isn't supported, so nothing is written.
+    client
+        .managed_zones()
+        .remove_iam_member("team-a", "roles/dns.reader", "user:bob@example.com")
+        .await
+        .unwrap();
+    assert_eq!(zone.lock().unwrap().requests.len(), 3);
+}
+
+#[tokio::test]
+async fn fails_on_concurrent_policy_change() {
+    let (client, _) = client(policy(), 1);
+
+    let err = client
+        .managed_zones()
+        .add_iam_member("team-a", "roles/dns.admin", "group:team-a@example.com")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DnsError::Conflict { .. }));
+    assert!(err.is_conflict());
+}
+
+#[tokio::test]
+async fn retries_concurrent_policy_change() {
+    let (client, zone) = client(policy(), 2);
+
+    let updated = client
+        .managed_zones()
+        .retry_conflicts(2)
+        .add_iam_member("team-a", "roles/dns.admin", "group:team-a@example.com")
+        .await
+        .unwrap();
+    assert_eq!(updated.etag.as_deref(), Some("etag-3"));
+    assert_eq!(zone.lock().unwrap().requests.len(), 6);
+}
+
+#[tokio::test]
+async fn tests_permissions() {
+    let (client, zone) = client(policy(), 0);
+
+    let granted = client
+        .managed_zones()
+        .test_iam_permissions(
+            "team-a",
+            vec!["dns.resourceRecordSets.list", "dns.managedZones.delete"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(granted, ["dns.resourceRecordSets.list"]);
+
+    let zone = zone.lock().unwrap();
+    let (path, body) = &zone.requests[0];
+    assert!(path.ends_with("/managedZones/team-a:testIamPermissions"));
+    assert_eq!(body["permissions"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use cloud_dns::{
+    api::managed_zone_operations::{ManagedZoneOperation, OperationResult, OperationStatus},
+    error::ErrorKind,
+    poll::PollOptions,
+    DnsClient,
+};
+use http::{Request, Response, StatusCode};
+use hyper::Body;
+
+/// The fixture operation with the given status.
+fn operation(status: &str) -> serde_json::Value {
+    let mut operation: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/operation.json")).unwrap();
+    operation["status"] = status.into();
+
+    operation
+}
+
+/// A client that reports the operation as pending until it has been fetched
+/// `polls_until_done` times, answering every fetch with `status` instead if
+/// it isn't a success.
+fn client(polls_until_done: usize, status: StatusCode) -> (DnsClient, Arc<AtomicUsize>) {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let counter = polls.clone();
+
+    let service = tower::service_fn(move |request: Request<Body>| {
+        let counter = counter.clone();
+
+        async move {
+            assert_eq!(
+                request.uri().path(),
+                "/dns/v1/projects/my-project/managedZones/example-com/operations/3"
+            );
+            let polls = counter.fetch_add(1, Ordering::SeqCst) + 1;
+
+            if !status.is_success() {
+                return Response::builder()
+                    .status(status)
+                    .body(Body::from(include_str!("fixtures/error_not_found.json")));
+            }
+
+            let status = if polls >= polls_until_done {
+                "done"
+            } else {
+                "pending"
+            };
+            Response::builder().body(Body::from(operation(status).to_string()))
+        }
+    });
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    (client, polls)
+}
+
+fn options() -> PollOptions {
+    PollOptions::default()
+        .initial_interval(Duration::from_millis(1))
+        .max_interval(Duration::from_millis(5))
+}
+
+fn pending() -> ManagedZoneOperation {
+    serde_json::from_value(operation("pending")).unwrap()
+}
+
+#[tokio::test]
+async fn waits_until_done() {
+    let (client, polls) = client(3, StatusCode::OK);
+
+    let operation = client
+        .managed_zone_operations()
+        .poller("example-com")
+        .options(options())
+        .wait(pending())
+        .await
+        .unwrap();
+
+    assert_eq!(operation.status, OperationStatus::Done);
+    assert_eq!(polls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn done_operations_are_not_polled() {
+    let (client, polls) = client(1, StatusCode::OK);
+    let done = serde_json::from_value(operation("done")).unwrap();
+
+    client
+        .managed_zone_operations()
+        .poller("example-com")
+        .wait(done)
+        .await
+        .unwrap();
+
+    assert_eq!(polls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn waits_for_result() {
+    let (client, _) = client(2, StatusCode::OK);
+
+    let result = client
+        .managed_zone_operations()
+        .poller("example-com")
+        .options(options())
+        .wait_for_result(pending())
+        .await
+        .unwrap();
+
+    match result {
+        Some(OperationResult::Zone(zone)) => assert_eq!(zone.name, "example-com"),
+        other => panic!("expected the updated zone, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn surfaces_errors() {
+    let (client, polls) = client(1, StatusCode::NOT_FOUND);
+
+    let error = client
+        .managed_zone_operations()
+        .poller("example-com")
+        .options(options())
+        .wait_for_result(pending())
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::NotFound);
+    assert_eq!(polls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn times_out() {
+    let (client, _) = client(usize::MAX, StatusCode::OK);
+
+    let error = client
+        .managed_zone_operations()
+        .poller("example-com")
+        .options(options().timeout(Duration::from_millis(50)))
+        .wait(pending())
+        .await
+        .unwrap_err();
+
+    assert!(error.is_timeout());
+}
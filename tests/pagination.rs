@@ -0,0 +1,221 @@
+//! Checks the query parameters of `list` calls, and that `list_all` streams
+//! follow `nextPageToken` across pages.
+
+use std::sync::{Arc, Mutex};
+
+use cloud_dns::{
+    api::{
+        changes::{ChangesSortBy, ListChangesRequest},
+        dns_keys::{DigestType, ListDnsKeysRequest},
+        managed_zone_operations::{ListOperationsRequest, OperationsSortBy},
+        managed_zones::ListManagedZonesRequest,
+        resource_record_sets::ListRecordSetsRequest,
+    },
+    pagination::{PageRequest, SortOrder},
+    rdata::RecordType,
+    DnsClient,
+};
+use futures::TryStreamExt;
+use http::{Request, Response};
+use hyper::Body;
+
+/// A client that answers list requests with `pages`, looked up by their
+/// `pageToken` (`None` for the first page), and records the requested URIs.
+fn client(pages: Vec<(Option<&'static str>, String)>) -> (DnsClient, Arc<Mutex<Vec<String>>>) {
+    let uris = Arc::new(Mutex::new(Vec::new()));
+
+    let service = {
+        let uris = uris.clone();
+
+        tower::service_fn(move |request: Request<Body>| {
+            uris.lock().unwrap().push(request.uri().to_string());
+
+            let page_token = request.uri().query().and_then(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .find(|(key, _)| key == "pageToken")
+                    .map(|(_, value)| value.into_owned())
+            });
+            let body = pages
+                .iter()
+                .find(|(token, _)| token.map(str::to_string) == page_token)
+                .map(|(_, body)| body.clone())
+                .expect("an unexpected page was requested");
+
+            async move { Ok::<_, hyper::Error>(Response::new(Body::from(body))) }
+        })
+    };
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    (client, uris)
+}
+
+/// A page of policies with the given names.
+fn page(names: &[&str], next_page_token: Option<&str>) -> String {
+    let policies: Vec<_> = names
+        .iter()
+        .map(|name| serde_json::json!({ "name": name }))
+        .collect();
+    let mut page = serde_json::json!({
+        "kind": "dns#policiesListResponse",
+        "policies": policies,
+    });
+    if let Some(token) = next_page_token {
+        page["nextPageToken"] = token.into();
+    }
+
+    page.to_string()
+}
+
+async fn list_names(client: &DnsClient) -> Vec<String> {
+    client
+        .policies()
+        .list_all(Default::default())
+        .map_ok(|policy| policy.name)
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn follows_next_page_tokens() {
+    let (client, uris) = client(vec![
+        (None, page(&["a", "b"], Some("page-2"))),
+        (Some("page-2"), page(&["c"], Some("page-3"))),
+        (Some("page-3"), page(&["d"], Some(""))),
+    ]);
+
+    assert_eq!(list_names(&client).await, ["a", "b", "c", "d"]);
+    assert_eq!(
+        *uris.lock().unwrap(),
+        [
+            "https://dns.googleapis.com/dns/v1/projects/my-project/policies",
+            "https://dns.googleapis.com/dns/v1/projects/my-project/policies?pageToken=page-2",
+            "https://dns.googleapis.com/dns/v1/projects/my-project/policies?pageToken=page-3",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stops_without_next_page_token() {
+    let (client, uris) = client(vec![(None, page(&["a"], None))]);
+
+    assert_eq!(list_names(&client).await, ["a"]);
+    assert_eq!(uris.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn empty_pages_are_followed() {
+    let (client, uris) = client(vec![
+        (None, page(&[], Some("page-2"))),
+        (Some("page-2"), page(&["a"], None)),
+    ]);
+
+    assert_eq!(list_names(&client).await, ["a"]);
+    assert_eq!(uris.lock().unwrap().len(), 2);
+}
+
+/// The URIs requested by `list`, relative to the project, answering every
+/// request with an empty page.
+async fn list_uris<F, Fut>(list: F) -> Vec<String>
+where
+    F: FnOnce(DnsClient) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let (client, uris) = client(vec![
+        (None, page(&[], None)),
+        (Some("abc"), page(&[], None)),
+    ]);
+    list(client).await;
+
+    let uris = uris.lock().unwrap();
+    uris.iter()
+        .map(|uri| {
+            uri.trim_start_matches("https://dns.googleapis.com/dns/v1/projects/my-project/")
+                .to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn page_parameters() {
+    let uris = list_uris(|client| async move {
+        let policies = client.policies();
+        policies.list(&PageRequest::new()).await.unwrap();
+        policies
+            .list(&PageRequest::new().max_results(10).page_token("abc"))
+            .await
+            .unwrap();
+    })
+    .await;
+
+    assert_eq!(uris, ["policies", "policies?maxResults=10&pageToken=abc"]);
+}
+
+#[tokio::test]
+async fn list_parameters() {
+    let uris = list_uris(|client| async move {
+        client
+            .managed_zones()
+            .list(
+                &ListManagedZonesRequest::new()
+                    .max_results(5)
+                    .dns_name("example.com."),
+            )
+            .await
+            .unwrap();
+        client
+            .changes()
+            .list(
+                "my-zone",
+                &ListChangesRequest::new()
+                    .sort_by(ChangesSortBy::ChangeSequence)
+                    .sort_order(SortOrder::Descending),
+            )
+            .await
+            .unwrap();
+        client
+            .managed_zone_operations()
+            .list(
+                "my-zone",
+                &ListOperationsRequest::new()
+                    .page_token("abc")
+                    .sort_by(OperationsSortBy::StartTime),
+            )
+            .await
+            .unwrap();
+        client
+            .dns_keys()
+            .list(
+                "my-zone",
+                &ListDnsKeysRequest::new().digest_type([DigestType::Sha1, DigestType::Sha256]),
+            )
+            .await
+            .unwrap();
+        client
+            .resource_record_sets()
+            .list(
+                "my-zone",
+                &ListRecordSetsRequest::new()
+                    .name("www.example.com.")
+                    .r#type(RecordType::AAAA),
+            )
+            .await
+            .unwrap();
+    })
+    .await;
+
+    assert_eq!(
+        uris,
+        [
+            "managedZones?maxResults=5&dnsName=example.com.",
+            "managedZones/my-zone/changes?sortBy=changeSequence&sortOrder=descending",
+            "managedZones/my-zone/operations?pageToken=abc&sortBy=startTime",
+            "managedZones/my-zone/dnsKeys?digestType=sha1%2Csha256",
+            "managedZones/my-zone/rrsets?name=www.example.com.&type=AAAA",
+        ]
+    );
+}
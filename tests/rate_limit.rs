@@ -0,0 +1,99 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use cloud_dns::{rate_limit::RateLimitMetrics, DnsClient, DnsClientBuilder};
+use http::{Request, Response};
+use hyper::Body;
+
+/// Builds a client whose responses take `delay`, tracking the highest number
+/// of requests in flight at once.
+fn client(builder: DnsClientBuilder, delay: Duration) -> (DnsClient, Arc<AtomicUsize>) {
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let max = max_in_flight.clone();
+
+    let service = tower::service_fn(move |_request: Request<Body>| {
+        let in_flight = in_flight.clone();
+        let max = max.clone();
+
+        async move {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(delay).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            Response::builder().body(Body::from(r#"{"name": "policy"}"#))
+        }
+    });
+
+    (builder.build(service).unwrap(), max_in_flight)
+}
+
+#[tokio::test]
+async fn limits_concurrent_requests() {
+    let builder = DnsClient::builder("my-project")
+        .disable_authentication()
+        .max_concurrent_requests(2);
+    let (client, max_in_flight) = client(builder, Duration::from_millis(20));
+
+    let requests = (0..6).map(|_| async { client.policies().get("policy").await });
+    for result in futures::future::join_all(requests).await {
+        result.unwrap();
+    }
+
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+
+    let metrics = client.rate_limit_metrics().unwrap();
+    assert_eq!(metrics.requests, 6);
+    assert!(metrics.delayed_requests >= 4);
+    assert!(metrics.max_wait >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn limits_requests_per_second() {
+    let builder = DnsClient::builder("my-project")
+        .disable_authentication()
+        .requests_per_second(20.0);
+    let (client, _) = client(builder, Duration::ZERO);
+
+    let start = Instant::now();
+    // The first 20 requests burst, the next 5 wait for the bucket to refill.
+    for _ in 0..25 {
+        client.policies().get("policy").await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    let metrics = client.rate_limit_metrics().unwrap();
+    assert_eq!(metrics.requests, 25);
+    assert!(metrics.total_wait >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn no_metrics_without_limits() {
+    let (client, _) = client(
+        DnsClient::builder("my-project").disable_authentication(),
+        Duration::ZERO,
+    );
+
+    client.policies().get("policy").await.unwrap();
+
+    assert_eq!(client.rate_limit_metrics(), None);
+}
+
+#[test]
+fn mean_wait_of_many_requests() {
+    let metrics = RateLimitMetrics {
+        requests: 1 << 33,
+        total_wait: Duration::from_secs(1 << 33),
+        ..Default::default()
+    };
+
+    assert_eq!(metrics.mean_wait(), Duration::from_secs(1));
+    assert_eq!(RateLimitMetrics::default().mean_wait(), Duration::ZERO);
+}
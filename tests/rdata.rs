@@ -0,0 +1,178 @@
+use cloud_dns::{
+    api::resource_record_sets::ResourceRecordSets,
+    rdata::{Mx, RData, RecordType, Srv, Svcb, Txt},
+};
+
+/// Parses `s` as `record_type` and asserts it renders back unchanged.
+fn round_trip(record_type: RecordType, s: &str) -> RData {
+    let rdata = RData::parse(record_type.clone(), s).unwrap();
+
+    assert_eq!(rdata.record_type(), record_type);
+    assert_eq!(rdata.to_string(), s);
+
+    rdata
+}
+
+#[test]
+fn record_types() {
+    for record_type in RecordType::ALL.iter() {
+        assert_eq!(
+            record_type.to_string().parse::<RecordType>().unwrap(),
+            *record_type
+        );
+    }
+
+    assert_eq!("aaaa".parse::<RecordType>().unwrap(), RecordType::AAAA);
+    assert!("WKS".parse::<RecordType>().is_err());
+}
+
+#[test]
+fn presentation_format() {
+    round_trip(RecordType::A, "192.0.2.1");
+    round_trip(RecordType::AAAA, "2001:db8::1");
+    round_trip(RecordType::CAA, "0 issue \"letsencrypt.org\"");
+    round_trip(RecordType::CNAME, "www.example.com.");
+    round_trip(
+        RecordType::DNSKEY,
+        "257 3 8 AwEAAbr2ZmxUtmcs9Sb7zG1bGJ+Ob2m6Yw==",
+    );
+    round_trip(RecordType::DS, "51033 8 2 6BB3F1BF8E1C5E7C");
+    round_trip(RecordType::HTTPS, "1 . alpn=h2,h3 no-default-alpn");
+    round_trip(
+        RecordType::IPSECKEY,
+        "10 1 2 192.0.2.38 AQNRU3mG7TVTO2BkR47usntb",
+    );
+    round_trip(
+        RecordType::NAPTR,
+        "100 10 \"S\" \"SIP+D2U\" \"\" _sip._udp.example.com.",
+    );
+    round_trip(
+        RecordType::SOA,
+        "ns-cloud-a1.googledomains.com. cloud-dns-hostmaster.google.com. 1 21600 3600 259200 300",
+    );
+    round_trip(RecordType::SSHFP, "4 2 123456789abcdef67890123456789abcdef");
+    round_trip(RecordType::TLSA, "3 1 1 0123456789abcdef");
+
+    assert_eq!(
+        round_trip(RecordType::MX, "10 mx1.example.com."),
+        RData::MX(Mx {
+            preference: 10,
+            exchange: "mx1.example.com.".to_string(),
+        })
+    );
+    assert_eq!(
+        round_trip(RecordType::SRV, "10 5 5060 sip.example.com."),
+        RData::SRV(Srv {
+            priority: 10,
+            weight: 5,
+            port: 5060,
+            target: "sip.example.com.".to_string(),
+        })
+    );
+}
+
+#[test]
+fn txt_escapes() {
+    let rdata = round_trip(
+        RecordType::TXT,
+        r#""v=spf1 -all" "quote \" and \\ backslash""#,
+    );
+
+    assert_eq!(
+        rdata,
+        RData::TXT(Txt(vec![
+            "v=spf1 -all".to_string(),
+            "quote \" and \\ backslash".to_string(),
+        ]))
+    );
+
+    let rdata = RData::parse(RecordType::TXT, r#"unquoted "caf\195\169""#).unwrap();
+    assert_eq!(
+        rdata,
+        RData::TXT(Txt(vec!["unquoted".to_string(), "café".to_string()]))
+    );
+    assert_eq!(rdata.to_string(), r#""unquoted" "caf\195\169""#);
+}
+
+#[test]
+fn txt_splits_long_values() {
+    assert_eq!(Txt::new("short"), Txt(vec!["short".to_string()]));
+    assert_eq!(Txt::new(""), Txt(vec![String::new()]));
+
+    let value = "a".repeat(600);
+    let txt = Txt::new(&value);
+    let lengths: Vec<_> = txt.0.iter().map(String::len).collect();
+    assert_eq!(lengths, [255, 255, 90]);
+    assert_eq!(txt.joined(), value);
+
+    // Multibyte characters are kept whole.
+    let value = format!("{}é", "a".repeat(254));
+    let txt = Txt::new(&value);
+    assert_eq!(txt.0, ["a".repeat(254), "é".to_string()]);
+}
+
+#[test]
+fn svcb_quoted_params() {
+    assert_eq!(
+        round_trip(
+            RecordType::SVCB,
+            "1 svc.example.com. alpn=\"h2 h3\" port=8443"
+        ),
+        RData::SVCB(Svcb {
+            priority: 1,
+            target: "svc.example.com.".to_string(),
+            params: vec![
+                ("alpn".to_string(), Some("h2 h3".to_string())),
+                ("port".to_string(), Some("8443".to_string())),
+            ],
+        })
+    );
+
+    // Quotes around a value without whitespace are dropped.
+    let rdata = RData::parse(RecordType::HTTPS, "1 . alpn=\"h2,h3\"").unwrap();
+    assert_eq!(rdata.to_string(), "1 . alpn=h2,h3");
+}
+
+#[test]
+fn invalid() {
+    assert!(RData::parse(RecordType::A, "2001:db8::1").is_err());
+    assert!(RData::parse(RecordType::MX, "mx1.example.com.").is_err());
+    assert!(RData::parse(RecordType::MX, "10 mx1.example.com. extra").is_err());
+    assert!(RData::parse(RecordType::TXT, "\"unterminated").is_err());
+    assert!(RData::parse(RecordType::CAA, "256 issue \"ca.example\"").is_err());
+}
+
+#[test]
+fn typed_rrdatas() {
+    let rrsets: ResourceRecordSets =
+        serde_json::from_str(include_str!("fixtures/rrsets_list.json")).unwrap();
+
+    for rrset in &rrsets.rrsets {
+        let rdatas = rrset.typed_rrdatas().unwrap();
+
+        assert_eq!(rdatas.len(), rrset.rrdatas.len());
+        assert!(rdatas.iter().all(|r| r.record_type() == rrset.r#type));
+    }
+}
+
+#[test]
+fn unknown_record_types() {
+    let rrsets: ResourceRecordSets = serde_json::from_str(
+        r#"{"kind": "dns#resourceRecordSetsListResponse", "rrsets": [{"kind": "dns#resourceRecordSet", "name": "example.com.", "type": "WKS", "ttl": 300, "rrdatas": ["192.0.2.1 6 25"]}]}"#,
+    )
+    .unwrap();
+
+    let rrset = &rrsets.rrsets[0];
+    assert_eq!(rrset.r#type, RecordType::Unknown("WKS".to_string()));
+    assert_eq!(rrset.r#type.to_string(), "WKS");
+    assert!(rrset.typed_rrdatas().is_err());
+
+    assert_eq!(
+        serde_json::to_value(&rrset.r#type).unwrap(),
+        serde_json::json!("WKS")
+    );
+    assert_eq!(
+        serde_json::from_value::<RecordType>(serde_json::json!("AAAA")).unwrap(),
+        RecordType::AAAA
+    );
+}
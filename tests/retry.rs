@@ -0,0 +1,152 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use cloud_dns::{
+    api::{changes::Change, policies::Policy},
+    retry::RetryConfig,
+    DnsClient,
+};
+use http::{Request, Response, StatusCode};
+use hyper::Body;
+
+/// A client whose first `failures` requests are answered with `status`.
+fn client(status: StatusCode, failures: usize) -> (DnsClient, Arc<AtomicUsize>) {
+    client_with_body(
+        status,
+        r#"{"error": {"code": 503, "message": "backendError"}}"#,
+        failures,
+    )
+}
+
+/// Like [`client`], but the failures have an error body of `body`.
+fn client_with_body(
+    status: StatusCode,
+    body: &'static str,
+    failures: usize,
+) -> (DnsClient, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let service = tower::service_fn(move |_request: Request<Body>| {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+
+        async move {
+            if attempt < failures {
+                Response::builder()
+                    .status(status)
+                    .header(http::header::RETRY_AFTER, "0")
+                    .body(Body::from(body))
+            } else {
+                Response::builder().body(Body::from(r#"{"name": "policy"}"#))
+            }
+        }
+    });
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .retry(
+            RetryConfig::default()
+                .max_attempts(3)
+                .initial_backoff(Duration::from_millis(1)),
+        )
+        .build(service)
+        .unwrap();
+
+    (client, calls)
+}
+
+#[tokio::test]
+async fn retries_idempotent_requests() {
+    let (client, calls) = client(StatusCode::SERVICE_UNAVAILABLE, 2);
+
+    let policy = client.policies().get("policy").await.unwrap();
+
+    assert_eq!(policy.name, "policy");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let (client, calls) = client(StatusCode::SERVICE_UNAVAILABLE, 5);
+
+    let error = client.policies().get("policy").await.unwrap_err();
+
+    assert!(error.is_retryable());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_unsafe_requests_on_server_errors() {
+    let (client, calls) = client(StatusCode::SERVICE_UNAVAILABLE, 1);
+
+    client
+        .policies()
+        .create(Policy::new("policy"))
+        .await
+        .unwrap_err();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn does_not_retry_changes_on_server_errors() {
+    let (client, calls) = client(StatusCode::INTERNAL_SERVER_ERROR, 1);
+
+    let error = client
+        .changes()
+        .create("example-com", Change::default())
+        .await
+        .unwrap_err();
+
+    assert!(error.is_retryable());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn retries_rate_limited_requests() {
+    let (client, calls) = client(StatusCode::TOO_MANY_REQUESTS, 1);
+
+    client
+        .policies()
+        .create(Policy::new("policy"))
+        .await
+        .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn retries_forbidden_requests_that_were_rate_limited() {
+    let (client, calls) = client_with_body(
+        StatusCode::FORBIDDEN,
+        r#"{"error": {"code": 403, "message": "Rate Limit Exceeded", "errors": [{"reason": "userRateLimitExceeded", "domain": "usageLimits"}]}}"#,
+        1,
+    );
+
+    client
+        .policies()
+        .create(Policy::new("policy"))
+        .await
+        .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn does_not_retry_forbidden_requests() {
+    let (client, calls) = client_with_body(
+        StatusCode::FORBIDDEN,
+        r#"{"error": {"code": 403, "message": "Forbidden", "errors": [{"reason": "forbidden"}]}}"#,
+        1,
+    );
+
+    let error = client.policies().get("policy").await.unwrap_err();
+
+    assert!(error.is_auth());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,350 @@
+use std::sync::{Arc, Mutex};
+
+use cloud_dns::{
+    api::{changes::Change, resource_record_sets::ResourceRecordSet},
+    rdata::RecordType,
+    sync::{self, SyncOptions},
+    DnsClient,
+};
+use http::{Method, Request, Response};
+use hyper::Body;
+use serde_json::json;
+
+/// A client for the zone `example.com.` containing `current`, which records
+/// the changes created through it. The project allows two additions and two
+/// deletions per change.
+fn client(current: Vec<ResourceRecordSet>) -> (DnsClient, Arc<Mutex<Vec<Change>>>) {
+    let created = Arc::new(Mutex::new(Vec::new()));
+    let changes = created.clone();
+
+    let service = tower::service_fn(move |request: Request<Body>| {
+        let current = current.clone();
+        let changes = changes.clone();
+
+        async move {
+            let path = request.uri().path().to_string();
+
+            let body = if request.method() == Method::POST {
+                let body = hyper::body::to_bytes(request.into_body()).await?;
+                let change: Change = serde_json::from_slice(&body).unwrap();
+                changes.lock().unwrap().push(change);
+                json!({"id": "1", "status": "pending"})
+            } else if path.ends_with("/managedZones/example") {
+                json!({"name": "example", "dnsName": "example.com."})
+            } else if path.ends_with("/rrsets") {
+                json!({"kind": "dns#resourceRecordSetsListResponse", "rrsets": current})
+            } else {
+                let mut project: serde_json::Value =
+                    serde_json::from_str(include_str!("fixtures/project.json")).unwrap();
+                project["quota"]["rrsetAdditionsPerChange"] = json!(2);
+                project["quota"]["rrsetDeletionsPerChange"] = json!(2);
+                project
+            };
+
+            Ok::<_, hyper::Error>(Response::new(Body::from(body.to_string())))
+        }
+    });
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    (client, created)
+}
+
+fn rrset(name: &str, r#type: RecordType, ttl: i32, rrdatas: &[&str]) -> ResourceRecordSet {
+    ResourceRecordSet::new(name, r#type, ttl, rrdatas.iter().copied())
+}
+
+fn apex() -> Vec<ResourceRecordSet> {
+    vec![
+        rrset(
+            "example.com.",
+            RecordType::SOA,
+            21600,
+            &["ns-cloud-a1.googledomains.com. cloud-dns-hostmaster.google.com. 1 21600 3600 259200 300"],
+        ),
+        rrset(
+            "example.com.",
+            RecordType::NS,
+            21600,
+            &["ns-cloud-a1.googledomains.com."],
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn plans_minimal_change() {
+    let mut current = apex();
+    current.extend(vec![
+        rrset(
+            "www.example.com.",
+            RecordType::A,
+            300,
+            &["192.0.2.1", "192.0.2.2"],
+        ),
+        rrset("old.example.com.", RecordType::A, 300, &["192.0.2.3"]),
+        rrset(
+            "mail.example.com.",
+            RecordType::MX,
+            300,
+            &["10 mx.example.com."],
+        ),
+    ]);
+    let (client, _) = client(current);
+
+    let desired = vec![
+        // Unchanged, apart from the order of the rrdatas.
+        rrset(
+            "www.example.com.",
+            RecordType::A,
+            300,
+            &["192.0.2.2", "192.0.2.1"],
+        ),
+        rrset(
+            "mail.example.com.",
+            RecordType::MX,
+            600,
+            &["10 mx.example.com."],
+        ),
+        rrset(
+            "new.example.com.",
+            RecordType::CNAME,
+            300,
+            &["www.example.com."],
+        ),
+    ];
+
+    let plan = sync::plan(&client, "example", desired, &SyncOptions::new())
+        .await
+        .unwrap();
+
+    let mut deletions: Vec<_> = plan
+        .change
+        .deletions
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    deletions.sort_unstable();
+    assert_eq!(deletions, ["mail.example.com.", "old.example.com."]);
+
+    let mut additions: Vec<_> = plan
+        .change
+        .additions
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    additions.sort_unstable();
+    assert_eq!(additions, ["mail.example.com.", "new.example.com."]);
+}
+
+#[tokio::test]
+async fn ignores_spelling_differences() {
+    let mut current = apex();
+    current.extend(vec![
+        rrset("txt.example.com.", RecordType::TXT, 300, &["\"foo\""]),
+        rrset("v6.example.com.", RecordType::AAAA, 300, &["2001:db8::a"]),
+    ]);
+    let (client, _) = client(current);
+
+    let desired = vec![
+        rrset("txt.example.com.", RecordType::TXT, 300, &["foo"]),
+        rrset(
+            "v6.example.com.",
+            RecordType::AAAA,
+            300,
+            &["2001:DB8:0:0:0:0:0:A"],
+        ),
+    ];
+
+    let plan = sync::plan(&client, "example", desired, &SyncOptions::new())
+        .await
+        .unwrap();
+    assert!(plan.is_empty());
+}
+
+#[tokio::test]
+async fn ignores_apex_records_by_default() {
+    let (client, _) = client(apex());
+
+    let plan = sync::plan(&client, "example", Vec::new(), &SyncOptions::new())
+        .await
+        .unwrap();
+    assert!(plan.is_empty());
+
+    let plan = sync::plan(
+        &client,
+        "example",
+        Vec::new(),
+        &SyncOptions::new().manage_apex(true),
+    )
+    .await
+    .unwrap();
+    assert_eq!(plan.change.deletions.len(), 2);
+}
+
+#[tokio::test]
+async fn only_manages_owned_names() {
+    let mut current = apex();
+    current.extend(vec![
+        rrset("owned.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+        rrset(
+            "owned.example.com.",
+            RecordType::TXT,
+            300,
+            &["\"managed-by=sync\""],
+        ),
+        rrset("foreign.example.com.", RecordType::A, 300, &["192.0.2.2"]),
+    ]);
+    let (client, _) = client(current);
+    let options = SyncOptions::new().ownership_marker("managed-by=sync");
+
+    let desired = vec![rrset(
+        "new.example.com.",
+        RecordType::A,
+        300,
+        &["192.0.2.3"],
+    )];
+    let plan = sync::plan(&client, "example", desired, &options)
+        .await
+        .unwrap();
+
+    // The owned name is removed along with its marker, the foreign one is kept.
+    let mut deletions: Vec<_> = plan
+        .change
+        .deletions
+        .iter()
+        .map(|r| (r.name.as_str(), r.r#type.clone()))
+        .collect();
+    deletions.sort_unstable();
+    assert_eq!(
+        deletions,
+        [
+            ("owned.example.com.", RecordType::A),
+            ("owned.example.com.", RecordType::TXT),
+        ]
+    );
+
+    // The new name is marked as owned.
+    let marker = plan
+        .change
+        .additions
+        .iter()
+        .find(|r| r.r#type == RecordType::TXT)
+        .unwrap();
+    assert_eq!(marker.name, "new.example.com.");
+    assert_eq!(marker.rrdatas, ["\"managed-by=sync\""]);
+
+    let desired = vec![rrset(
+        "foreign.example.com.",
+        RecordType::A,
+        300,
+        &["192.0.2.4"],
+    )];
+    let err = sync::plan(&client, "example", desired, &options)
+        .await
+        .unwrap_err();
+    assert!(err.is_conflict());
+}
+
+/// Syncing a name that holds foreign record sets of other types must not mark
+/// it as owned, or the next run would delete them.
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn does_not_take_over_names_with_foreign_records() {
+    use cloud_dns::{api::managed_zones::ManagedZone, testing::FakeDnsServer};
+
+    let server = FakeDnsServer::new();
+    let client = server.client("my-project");
+    client
+        .managed_zones()
+        .create(ManagedZone::public("example", "example.com."))
+        .await
+        .unwrap();
+    let foreign = rrset("x.example.com.", RecordType::A, 300, &["192.0.2.1"]);
+    client
+        .changes()
+        .create("example", Change::new(vec![foreign], Vec::new()))
+        .await
+        .unwrap();
+
+    // Each run plans and applies, as a sync job would.
+    let options = SyncOptions::new().ownership_marker("owner=me");
+    for _ in 0..2 {
+        let desired = vec![rrset(
+            "x.example.com.",
+            RecordType::AAAA,
+            300,
+            &["2001:db8::1"],
+        )];
+        match sync::plan(&client, "example", desired, &options).await {
+            Ok(plan) => {
+                sync::apply(&client, &plan).await.unwrap();
+            }
+            Err(err) => assert!(err.is_conflict(), "unexpected error {:?}", err),
+        }
+    }
+
+    let types: Vec<_> = server
+        .record_sets("example")
+        .into_iter()
+        .filter(|rrset| rrset.name == "x.example.com.")
+        .map(|rrset| rrset.r#type)
+        .collect();
+    assert_eq!(types, [RecordType::A]);
+}
+
+#[tokio::test]
+async fn rejects_duplicate_record_sets() {
+    let (client, _) = client(apex());
+
+    let desired = vec![
+        rrset("www.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+        rrset("WWW.example.com.", RecordType::A, 300, &["192.0.2.2"]),
+    ];
+    let err = sync::plan(&client, "example", desired, &SyncOptions::new())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.kind(),
+        cloud_dns::error::ErrorKind::InvalidArgument
+    ));
+}
+
+#[tokio::test]
+async fn applies_in_batches_within_quota() {
+    let mut current = apex();
+    current.extend(vec![
+        rrset("a.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+        rrset("b.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+    ]);
+    let (client, created) = client(current);
+
+    let desired = vec![
+        rrset("a.example.com.", RecordType::A, 300, &["192.0.2.2"]),
+        rrset("c.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+        rrset("d.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+        rrset("e.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+    ];
+    let plan = sync::plan(&client, "example", desired, &SyncOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(plan.change.additions.len(), 4);
+    assert_eq!(plan.change.deletions.len(), 2);
+
+    let changes = sync::apply(&client, &plan).await.unwrap();
+    assert_eq!(changes.len(), 2);
+
+    let created = created.lock().unwrap();
+    assert_eq!(created.len(), 2);
+    for change in created.iter() {
+        assert!(change.additions.len() <= 2 && change.deletions.len() <= 2);
+    }
+
+    // The replacement of `a` is applied atomically.
+    let first = &created[0];
+    assert_eq!(first.additions[0].name, "a.example.com.");
+    assert_eq!(first.deletions[0].name, "a.example.com.");
+}
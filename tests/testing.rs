@@ -0,0 +1,386 @@
+#![cfg(feature = "testing")]
+
+use std::time::Duration;
+
+use cloud_dns::{
+    api::{
+        changes::{Change, Status},
+        managed_zone_operations::OperationResult,
+        managed_zones::{DnsSecConfig, DnssecState, ManagedZone},
+        policies::Policy,
+        resource_record_sets::{
+            ListRecordSetsRequest, RRSetRoutingPolicy, ResourceRecordSet, WrrPolicyItem,
+        },
+    },
+    poll::PollOptions,
+    rdata::RecordType,
+    retry::RetryConfig,
+    testing::FakeDnsServer,
+    DnsClient,
+};
+use http::StatusCode;
+
+async fn client_with_zone(server: &FakeDnsServer) -> DnsClient {
+    let client = server.client("my-project");
+    client
+        .managed_zones()
+        .create(ManagedZone::public("example", "example.com."))
+        .await
+        .unwrap();
+
+    client
+}
+
+fn www(rrdata: &str) -> ResourceRecordSet {
+    ResourceRecordSet::new("www.example.com.", RecordType::A, 300, vec![rrdata])
+}
+
+#[tokio::test]
+async fn manages_zones() {
+    let server = FakeDnsServer::new();
+    let client = client_with_zone(&server).await;
+
+    let zone = client
+        .managed_zones()
+        .get("example".to_string())
+        .await
+        .unwrap();
+    assert_eq!(zone.dns_name, "example.com.");
+    assert_eq!(zone.name_servers.len(), 4);
+
+    // New zones contain their SOA and NS records.
+    let types: Vec<_> = server
+        .record_sets("example")
+        .iter()
+        .map(|rrset| rrset.r#type.clone())
+        .collect();
+    assert_eq!(types, [RecordType::NS, RecordType::SOA]);
+
+    let err = client
+        .managed_zones()
+        .create(ManagedZone::public("example", "example.com."))
+        .await
+        .unwrap_err();
+    assert!(err.is_conflict());
+
+    client.managed_zones().delete("example").await.unwrap();
+    let err = client
+        .managed_zones()
+        .get("example".to_string())
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[tokio::test]
+async fn patches_only_the_given_zone_fields() {
+    let server = FakeDnsServer::new();
+    let client = server.client("my-project");
+    client
+        .managed_zones()
+        .create(ManagedZone::public("example", "example.com.").description("Example zone"))
+        .await
+        .unwrap();
+
+    client
+        .managed_zones()
+        .patch(
+            "example",
+            ManagedZone::public("example", "example.com.").label("team", "dns"),
+        )
+        .await
+        .unwrap();
+
+    let zone = client
+        .managed_zones()
+        .get("example".to_string())
+        .await
+        .unwrap();
+    assert_eq!(zone.description.as_deref(), Some("Example zone"));
+    assert_eq!(zone.labels["team"], "dns");
+}
+
+#[tokio::test]
+async fn applies_changes() {
+    let server = FakeDnsServer::new().pending_polls(2);
+    let client = client_with_zone(&server).await;
+
+    let change = client
+        .changes()
+        .create("example", Change::new(vec![www("192.0.2.1")], Vec::new()))
+        .await
+        .unwrap();
+    assert_eq!(change.status, Some(Status::Pending));
+
+    let options = PollOptions::default().initial_interval(Duration::from_millis(1));
+    let change = client
+        .changes()
+        .wait_until_done("example", change.id.as_deref().unwrap(), &options)
+        .await
+        .unwrap();
+    assert_eq!(change.status, Some(Status::Done));
+
+    let rrset = client
+        .resource_record_sets()
+        .get("example", "www.example.com.", RecordType::A)
+        .await
+        .unwrap();
+    assert_eq!(rrset.rrdatas, ["192.0.2.1"]);
+
+    // Deletions must match the current record set exactly.
+    let err = client
+        .changes()
+        .create(
+            "example",
+            Change::new(vec![www("192.0.2.3")], vec![www("192.0.2.2")]),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.is_precondition_failed());
+
+    client
+        .resource_record_sets()
+        .upsert("example", www("192.0.2.3"))
+        .await
+        .unwrap();
+    assert_eq!(
+        server.record_sets("example")[2].rrdatas,
+        ["192.0.2.3".to_string()]
+    );
+
+    // Non-empty zones can't be deleted.
+    let err = client.managed_zones().delete("example").await.unwrap_err();
+    assert_eq!(err.kind(), cloud_dns::error::ErrorKind::InvalidArgument);
+}
+
+#[tokio::test]
+async fn creates_routed_record_sets() {
+    let server = FakeDnsServer::new();
+    let client = client_with_zone(&server).await;
+
+    let routed = ResourceRecordSet::routed(
+        "wrr.example.com.",
+        RecordType::A,
+        300,
+        RRSetRoutingPolicy::wrr(vec![WrrPolicyItem::new(1.0, vec!["192.0.2.10"])]),
+    );
+    client
+        .changes()
+        .create("example", Change::new(vec![routed.clone()], Vec::new()))
+        .await
+        .unwrap();
+
+    let rrset = client
+        .resource_record_sets()
+        .get("example", "wrr.example.com.", RecordType::A)
+        .await
+        .unwrap();
+    assert!(rrset.rrdatas.is_empty());
+    assert_eq!(rrset.routing_policy, routed.routing_policy);
+
+    // Plain record sets still need rrdatas.
+    let mut empty = www("192.0.2.1");
+    empty.rrdatas.clear();
+    let err = client
+        .changes()
+        .create("example", Change::new(vec![empty], Vec::new()))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("at least one rrdata"), "{}", err);
+}
+
+#[tokio::test]
+async fn paginates_lists() {
+    let server = FakeDnsServer::new().page_size(2);
+    let client = client_with_zone(&server).await;
+
+    let additions = (0..5)
+        .map(|i| {
+            ResourceRecordSet::new(
+                &format!("host{}.example.com.", i),
+                RecordType::A,
+                300,
+                vec!["192.0.2.1"],
+            )
+        })
+        .collect();
+    client
+        .changes()
+        .create("example", Change::new(additions, Vec::new()))
+        .await
+        .unwrap();
+
+    let page = client
+        .resource_record_sets()
+        .list("example", &ListRecordSetsRequest::new())
+        .await
+        .unwrap();
+    assert_eq!(page.rrsets.len(), 2);
+    assert!(page.envelope.next_page_token.is_some());
+
+    let all = client
+        .resource_record_sets()
+        .list_all("example", ListRecordSetsRequest::new())
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 7);
+
+    let filtered = client
+        .resource_record_sets()
+        .list_all(
+            "example",
+            ListRecordSetsRequest::new()
+                .name("host3.example.com.")
+                .r#type(RecordType::A),
+        )
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(filtered.len(), 1);
+}
+
+#[tokio::test]
+async fn records_operations_and_keys() {
+    let server = FakeDnsServer::new();
+    let client = client_with_zone(&server).await;
+
+    let zone = ManagedZone::public("example", "example.com.").dnssec_config(DnsSecConfig {
+        state: Some(DnssecState::On),
+        ..DnsSecConfig::default()
+    });
+    let operation = client.managed_zones().patch("example", zone).await.unwrap();
+
+    let result = client
+        .managed_zone_operations()
+        .poller("example")
+        .wait_for_result(operation)
+        .await
+        .unwrap();
+    match result {
+        Some(OperationResult::Zone(zone)) => {
+            assert_eq!(zone.dnssec_config.unwrap().state, Some(DnssecState::On))
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let keys = client
+        .dns_keys()
+        .list("example", &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(keys.dns_keys.len(), 2);
+}
+
+#[tokio::test]
+async fn manages_policies() {
+    let server = FakeDnsServer::new();
+    let client = server.client("my-project");
+
+    let policy = client
+        .policies()
+        .create(Policy::new("policy"))
+        .await
+        .unwrap();
+    assert!(policy.id.is_some());
+
+    let updated = client
+        .policies()
+        .patch("policy", Policy::new("policy").description("updated"))
+        .await
+        .unwrap();
+    assert_eq!(updated.policy.description.as_deref(), Some("updated"));
+
+    let network =
+        "https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default";
+    let attached = client
+        .policies()
+        .attach_network("policy", network)
+        .await
+        .unwrap();
+    assert_eq!(attached.networks[0].network_url, network);
+    assert_eq!(attached.description.as_deref(), Some("updated"));
+
+    // The same network by its shorter URL is already attached.
+    let attached = client
+        .policies()
+        .attach_network("policy", "projects/my-project/global/networks/default")
+        .await
+        .unwrap();
+    assert_eq!(attached.networks.len(), 1);
+
+    let detached = client
+        .policies()
+        .detach_network("policy", network)
+        .await
+        .unwrap();
+    assert!(detached.networks.is_empty());
+
+    client.policies().delete("policy").await.unwrap();
+    let policies = client.policies().list(&Default::default()).await.unwrap();
+    assert!(policies.policies.is_empty());
+}
+
+#[tokio::test]
+async fn patches_only_the_given_policy_fields() {
+    let server = FakeDnsServer::new();
+    let client = server.client("my-project");
+    client
+        .policies()
+        .create(
+            Policy::new("policy")
+                .description("Forward to on-prem")
+                .enable_inbound_forwarding(true)
+                .enable_logging(true),
+        )
+        .await
+        .unwrap();
+
+    let network =
+        "https://www.googleapis.com/compute/v1/projects/my-project/global/networks/default";
+    let updated = client
+        .policies()
+        .patch("policy", Policy::new("policy").network(network))
+        .await
+        .unwrap()
+        .policy;
+
+    assert_eq!(updated.networks.len(), 1);
+    assert_eq!(updated.description.as_deref(), Some("Forward to on-prem"));
+    assert_eq!(updated.enable_inbound_forwarding, Some(true));
+    assert_eq!(updated.enable_logging, Some(true));
+
+    // Flags can still be turned off.
+    let updated = client
+        .policies()
+        .patch("policy", Policy::new("policy").enable_logging(false))
+        .await
+        .unwrap()
+        .policy;
+    assert_eq!(updated.enable_logging, Some(false));
+    assert_eq!(updated.enable_inbound_forwarding, Some(true));
+}
+
+#[tokio::test]
+async fn injects_errors() {
+    let server = FakeDnsServer::new();
+    server.fail_next_request(StatusCode::SERVICE_UNAVAILABLE, "backendError");
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .retry(RetryConfig::default().initial_backoff(Duration::from_millis(1)))
+        .build(server.clone())
+        .unwrap();
+
+    let project = client.projects().get().await.unwrap();
+    assert_eq!(project.id, "my-project");
+
+    server.fail_next_request(StatusCode::TOO_MANY_REQUESTS, "rateLimitExceeded");
+    let err = server
+        .client("my-project")
+        .projects()
+        .get()
+        .await
+        .unwrap_err();
+    assert!(err.is_rate_limited());
+}
@@ -0,0 +1,136 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use cloud_dns::{
+    api::{changes::Change, resource_record_sets::ResourceRecordSet},
+    error::DnsError,
+    rdata::RecordType,
+    DnsClient,
+};
+use http::{Method, Request, Response, StatusCode};
+use hyper::Body;
+
+const PRECONDITION_FAILED: &str = r#"{"error": {"code": 412, "message": "Precondition not met for 'entity.change.deletions[0]'", "errors": [{"domain": "global", "reason": "conditionNotMet", "message": "Precondition not met for 'entity.change.deletions[0]'"}]}}"#;
+
+/// A client for a zone where `www.example.com.` has an `A` record with
+/// `current` if set, whose first `conflicts` changes fail their precondition.
+fn client(current: Option<&'static str>, conflicts: usize) -> (DnsClient, Arc<Mutex<Vec<Change>>>) {
+    let created = Arc::new(Mutex::new(Vec::new()));
+    let changes = created.clone();
+    let attempts = Arc::new(AtomicUsize::new(0));
+
+    let service = tower::service_fn(move |request: Request<Body>| {
+        let changes = changes.clone();
+        let attempts = attempts.clone();
+
+        async move {
+            let response = Response::builder();
+
+            if request.method() == Method::POST {
+                let body = hyper::body::to_bytes(request.into_body()).await?;
+                changes
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(&body).unwrap());
+
+                if attempts.fetch_add(1, Ordering::SeqCst) < conflicts {
+                    return Ok::<_, hyper::Error>(
+                        response
+                            .status(StatusCode::PRECONDITION_FAILED)
+                            .body(Body::from(PRECONDITION_FAILED))
+                            .unwrap(),
+                    );
+                }
+
+                return Ok(response
+                    .body(Body::from(r#"{"id": "1", "status": "pending"}"#))
+                    .unwrap());
+            }
+
+            Ok(match current {
+                Some(rrdata) => response
+                    .body(Body::from(format!(
+                        r#"{{"name": "www.example.com.", "type": "A", "ttl": 300, "rrdatas": ["{}"]}}"#,
+                        rrdata
+                    )))
+                    .unwrap(),
+                None => response
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::from(include_str!("fixtures/error_not_found.json")))
+                    .unwrap(),
+            })
+        }
+    });
+
+    let client = DnsClient::builder("my-project")
+        .disable_authentication()
+        .build(service)
+        .unwrap();
+
+    (client, created)
+}
+
+fn www(rrdata: &str) -> ResourceRecordSet {
+    ResourceRecordSet::new("www.example.com.", RecordType::A, 300, vec![rrdata])
+}
+
+#[tokio::test]
+async fn upsert_replaces_existing_record_set() {
+    let (client, created) = client(Some("192.0.2.1"), 0);
+
+    client
+        .resource_record_sets()
+        .upsert("example", www("192.0.2.2"))
+        .await
+        .unwrap();
+
+    let created = created.lock().unwrap();
+    assert_eq!(created[0].deletions, [www("192.0.2.1")]);
+    assert_eq!(created[0].additions, [www("192.0.2.2")]);
+}
+
+#[tokio::test]
+async fn upsert_creates_missing_record_set() {
+    let (client, created) = client(None, 0);
+
+    client
+        .resource_record_sets()
+        .upsert("example", www("192.0.2.2"))
+        .await
+        .unwrap();
+
+    let created = created.lock().unwrap();
+    assert!(created[0].deletions.is_empty());
+    assert_eq!(created[0].additions, [www("192.0.2.2")]);
+}
+
+#[tokio::test]
+async fn fails_with_conflict_when_precondition_fails() {
+    let (client, created) = client(Some("192.0.2.1"), 1);
+
+    let err = client
+        .resource_record_sets()
+        .replace_many("example", vec![www("192.0.2.2")])
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, DnsError::Conflict { .. }));
+    assert!(err.is_conflict());
+    assert_eq!(created.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn retries_conflicts() {
+    let (client, created) = client(Some("192.0.2.1"), 2);
+
+    client
+        .resource_record_sets()
+        .retry_conflicts(2)
+        .replace_many("example", vec![www("192.0.2.2")])
+        .await
+        .unwrap();
+
+    assert_eq!(created.lock().unwrap().len(), 3);
+}
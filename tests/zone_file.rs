@@ -0,0 +1,233 @@
+use cloud_dns::{
+    api::resource_record_sets::{RRSetRoutingPolicy, ResourceRecordSet, WrrPolicyItem},
+    rdata::RecordType,
+    zone_file,
+};
+
+fn rrset(name: &str, r#type: RecordType, ttl: i32, rrdatas: &[&str]) -> ResourceRecordSet {
+    ResourceRecordSet::new(name, r#type, ttl, rrdatas.iter().copied())
+}
+
+#[test]
+fn parses_zone_file() {
+    let rrsets =
+        zone_file::parse_file("tests/fixtures/zones/example.com.zone", "example.com.").unwrap();
+
+    assert_eq!(
+        rrsets,
+        vec![
+            rrset(
+                "example.com.",
+                RecordType::SOA,
+                3600,
+                &["ns1.example.com. hostmaster.example.com. 2024010101 7200 900 604800 300"],
+            ),
+            rrset(
+                "example.com.",
+                RecordType::NS,
+                3600,
+                &["ns1.example.com.", "ns2.example.net."],
+            ),
+            rrset(
+                "example.com.",
+                RecordType::MX,
+                3600,
+                &["10 mail.example.com."]
+            ),
+            rrset(
+                "example.com.",
+                RecordType::TXT,
+                3600,
+                &["\"v=spf1 mx -all\""]
+            ),
+            rrset("ns1.example.com.", RecordType::A, 3600, &["192.0.2.1"]),
+            rrset(
+                "mail.example.com.",
+                RecordType::A,
+                300,
+                &["192.0.2.10", "192.0.2.11"],
+            ),
+            rrset(
+                "www.example.com.",
+                RecordType::CNAME,
+                3600,
+                &["example.com."]
+            ),
+            rrset(
+                "txt.example.com.",
+                RecordType::TXT,
+                3600,
+                &[r#""a \"quoted\" string; with semicolon" "second""#],
+            ),
+            rrset(
+                "_sip._tcp.services.example.com.",
+                RecordType::SRV,
+                600,
+                &["10 60 5060 sip.services.example.com."],
+            ),
+            // Inherits the `$TTL` of the including file.
+            rrset(
+                "sip.services.example.com.",
+                RecordType::A,
+                3600,
+                &["192.0.2.20"]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn parses_quoted_svc_params() {
+    let input = r#"$TTL 300
+@ IN HTTPS 1 . alpn="h2,h3"
+svc IN SVCB 1 svc.example.com. port="8443" alpn="h2 h3"
+"#;
+
+    let rrsets = zone_file::parse(input, "example.com.").unwrap();
+
+    assert_eq!(
+        rrsets,
+        [
+            rrset("example.com.", RecordType::HTTPS, 300, &["1 . alpn=h2,h3"]),
+            rrset(
+                "svc.example.com.",
+                RecordType::SVCB,
+                300,
+                &[r#"1 svc.example.com. port=8443 alpn="h2 h3""#]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn reports_line_numbers() {
+    let input = "$TTL 300\nwww IN A 192.0.2.1\n\nmail IN MX (\n  10\n)\n";
+
+    let err = zone_file::parse(input, "example.com").unwrap_err();
+
+    assert_eq!(err.line, 4);
+    assert_eq!(
+        err.to_string(),
+        "line 4: invalid record data: missing exchange"
+    );
+}
+
+#[test]
+fn rejects_records_without_ttl() {
+    let err = zone_file::parse("www IN A 192.0.2.1", "example.com.").unwrap_err();
+
+    assert_eq!(err.line, 1);
+    assert!(err.message.contains("TTL"), "{}", err);
+}
+
+#[test]
+fn reports_errors_in_included_files() {
+    let err = zone_file::parse("$INCLUDE missing.zone", "example.com.").unwrap_err();
+
+    assert_eq!(err.path.unwrap().to_str(), Some("missing.zone"));
+}
+
+#[test]
+fn renders_canonical_zone_file() {
+    let rrsets = vec![
+        rrset(
+            "www.example.com.",
+            RecordType::CNAME,
+            300,
+            &["example.com."],
+        ),
+        rrset(
+            "txt.example.com.",
+            RecordType::TXT,
+            300,
+            &[r#""say \"hi\"""#, "unquoted"],
+        ),
+        rrset(
+            "example.com.",
+            RecordType::A,
+            300,
+            &["192.0.2.2", "192.0.2.1"],
+        ),
+        rrset("example.com.", RecordType::NS, 21600, &["ns1.example.net."]),
+        rrset("a.b.example.com.", RecordType::AAAA, 60, &["2001:db8::1"]),
+        rrset("other.example.org.", RecordType::A, 300, &["192.0.2.3"]),
+    ];
+
+    let rendered = zone_file::render("example.com.", &rrsets);
+
+    assert_eq!(
+        rendered,
+        "\
+$ORIGIN example.com.
+$TTL 300
+
+@                  21600 IN NS    ns1.example.net.
+@                        IN A     192.0.2.1
+@                        IN A     192.0.2.2
+a.b                   60 IN AAAA  2001:db8::1
+txt                      IN TXT   \"say \\\"hi\\\"\"
+txt                      IN TXT   \"unquoted\"
+www                      IN CNAME example.com.
+other.example.org.       IN A     192.0.2.3
+"
+    );
+
+    // Rendering is stable, and the output parses back to the same records.
+    let parsed = zone_file::parse(&rendered, "example.com.").unwrap();
+    assert_eq!(parsed.len(), rrsets.len());
+    assert_eq!(zone_file::render("example.com.", &parsed), rendered);
+}
+
+#[test]
+fn renders_record_sets_without_ttl() {
+    let mut www = rrset("www.example.com.", RecordType::A, 0, &["192.0.2.1"]);
+    www.ttl = None;
+
+    let rendered = zone_file::render("example.com.", &[www]);
+
+    assert_eq!(
+        rendered,
+        "\
+$ORIGIN example.com.
+$TTL 300
+
+www  IN A 192.0.2.1
+"
+    );
+
+    let parsed = zone_file::parse(&rendered, "example.com.").unwrap();
+    assert_eq!(parsed[0].ttl, Some(zone_file::DEFAULT_TTL));
+    assert_eq!(zone_file::render("example.com.", &parsed), rendered);
+}
+
+#[test]
+fn notes_omitted_routing_policies() {
+    let rrsets = vec![
+        rrset("www.example.com.", RecordType::A, 300, &["192.0.2.1"]),
+        ResourceRecordSet::routed(
+            "wrr.example.com.",
+            RecordType::A,
+            300,
+            RRSetRoutingPolicy::wrr(vec![WrrPolicyItem::new(1.0, vec!["192.0.2.10"])]),
+        ),
+    ];
+
+    let rendered = zone_file::render("example.com.", &rrsets);
+
+    assert_eq!(
+        rendered,
+        "\
+$ORIGIN example.com.
+$TTL 300
+
+; Omitted routing policies, which zone files can't express:
+;   wrr.example.com. A
+
+www  IN A 192.0.2.1
+"
+    );
+    assert_eq!(
+        zone_file::parse(&rendered, "example.com.").unwrap().len(),
+        1
+    );
+}